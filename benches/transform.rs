@@ -0,0 +1,88 @@
+//! Compares the ways of transforming the monkey's vertices, and clearing the frame buffer. Run with `cargo bench`.
+
+use meenle_noonle::{demo, meshes, transform, Axis, Mat3x3, Rect, Renderer};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 2000;
+
+/// Runs `f` [ITERATIONS] times and prints how long each run took on average.
+fn bench(name: &str, mut f: impl FnMut()) -> Duration {
+    f(); // warm up
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let per_iteration = start.elapsed() / ITERATIONS;
+    println!("{name:>32}: {per_iteration:?}");
+    per_iteration
+}
+
+/// Like [bench], but runs `setup` on `state` before each run of `f` and only times `f`.
+fn bench_with_setup<S>(
+    name: &str,
+    state: &mut S,
+    mut setup: impl FnMut(&mut S),
+    mut f: impl FnMut(&mut S),
+) -> Duration {
+    setup(state);
+    f(state); // warm up
+    let mut elapsed = Duration::ZERO;
+    for _ in 0..ITERATIONS {
+        setup(state);
+        let start = Instant::now();
+        f(state);
+        elapsed += start.elapsed();
+    }
+    let per_iteration = elapsed / ITERATIONS;
+    println!("{name:>32}: {per_iteration:?}");
+    per_iteration
+}
+
+fn main() {
+    // rotating keeps the monkey the same size, so it can be transformed over and over
+    let mut monkey = meshes::monkey();
+    let mat = Mat3x3::rot(0.5, Axis::Y);
+
+    let per_vertex = bench("per vertex (Mat3x3::rot * Vec3)", || {
+        for vert in black_box(&mut monkey).verts_mut() {
+            *vert = Mat3x3::rot(0.5, Axis::Y) * *vert;
+        }
+    });
+    let scalar = bench("transform_scalar", || {
+        transform::transform_scalar(&mat, black_box(&mut monkey).verts_mut());
+    });
+    let simd = bench("transform", || {
+        transform::transform(&mat, black_box(&mut monkey).verts_mut());
+    });
+    println!(
+        "{:>32}: {:.2}x over per vertex, {:.2}x over scalar",
+        "speed-up",
+        per_vertex.as_secs_f64() / simd.as_secs_f64(),
+        scalar.as_secs_f64() / simd.as_secs_f64()
+    );
+
+    // fill_buffer only restores what was drawn since it last ran, so draw something first each time
+    let mut renderer = Renderer::new();
+    renderer.generate_background();
+    let demo_monkey = demo::mesh(0).unwrap();
+    let clear = |renderer: &mut Renderer| {
+        renderer.fill_buffer();
+        black_box(renderer.buffer());
+    };
+    bench_with_setup(
+        "fill_buffer after the monkey",
+        &mut renderer,
+        |renderer| renderer.render_mesh(&demo_monkey),
+        clear,
+    );
+    bench_with_setup(
+        "fill_buffer, whole frame",
+        &mut renderer,
+        |renderer| {
+            let buffer = renderer.buffer_mut();
+            buffer.mark_drawn(Rect::full(buffer.width(), buffer.height()));
+        },
+        clear,
+    );
+}
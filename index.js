@@ -4,7 +4,8 @@
 const WIDTH = 500;
 const HEIGHT = 500;
 const BUFSIZE = WIDTH * HEIGHT * 4;
-const ROTRATE = 5; //seconds per rotation
+const BACKGROUND_COUNT = 7;
+const MESH_NAMES = ["monkey", "icosphere", "cube", "tentacle"];
 
 const WASM_URL = "meenle-noonle.wasm";
 
@@ -27,31 +28,82 @@ Your browser is likely old, please upgrade.")
 unavailable. no Meenle-Noonle for you! you are missing out! :(";
 }
 
-instance.exports.generate_background();
-instance.exports.set_mesh(0);
+const renderer = instance.exports.mn_create();
+instance.exports.mn_generate_background(renderer);
+instance.exports.mn_set_mesh(renderer, 0);
+let meshId = 0;
+let lineWidth = 1;
+let backgroundId = 0;
+let stereoId = 0;
+let fogId = 0;
+let autoSpin = true;
 document.addEventListener('keydown', function(event) {
+    // shift and a number toggles one of the effects in src/effects.rs
+    const digit = event.code.match(/^Digit([1-6])$/);
+    if (event.shiftKey && digit) {
+        instance.exports.mn_toggle_effect(renderer, Number(digit[1]) - 1);
+        return;
+    }
     switch (event.code) {
         case "Digit1":
-            instance.exports.set_mesh(0);
+            meshId = 0;
+            instance.exports.mn_set_mesh(renderer, meshId);
             break;
         case "Digit2":
-            instance.exports.set_mesh(1);
+            meshId = 1;
+            instance.exports.mn_set_mesh(renderer, meshId);
             break;
         case "Digit3":
-            instance.exports.set_mesh(2);
+            meshId = 2;
+            instance.exports.mn_set_mesh(renderer, meshId);
+            break;
+        case "Digit4":
+            meshId = 3;
+            instance.exports.mn_set_mesh(renderer, meshId);
+            break;
+        case "KeyB":
+            backgroundId = (backgroundId + 1) % BACKGROUND_COUNT;
+            instance.exports.mn_set_demo_background(renderer, backgroundId);
+            break;
+        case "KeyD":
+            // off, then each stereo mode in src/stereo.rs in turn
+            stereoId = (stereoId + 1) % 5;
+            instance.exports.mn_set_stereo(renderer, stereoId - 1, 40);
+            break;
+        case "KeyF":
+            // off, linear, then exponential fog, fading into the background
+            fogId = (fogId + 1) % 3;
+            instance.exports.mn_set_fog(renderer, fogId - 1, -100, 150, 0xffffffff);
+            break;
+        case "BracketLeft":
+        case "BracketRight":
+            // thinner and thicker lines
+            lineWidth = Math.min(Math.max(lineWidth + (event.code == "BracketLeft" ? -1 : 1), 1), 8);
+            instance.exports.mn_set_line_width(renderer, lineWidth);
+            break;
+        case "KeyH":
+            showHud = !showHud;
+            break;
+        case "Space":
+            autoSpin = !autoSpin;
+            instance.exports.mn_orbit_set_auto_spin(renderer, autoSpin);
+            event.preventDefault();
+            break;
+        case "KeyR":
+            instance.exports.mn_orbit_reset(renderer);
             break;
     }
 });
 
 
-let bufptr = instance.exports.get_buffer();
+let bufptr = instance.exports.mn_get_buffer(renderer);
 let buffer = new Uint8ClampedArray(instance.exports.memory.buffer, bufptr, BUFSIZE);
 let imgData, imgBmap;
 if (!bmapSupport) { console.warn("ImageBitmap not supported. Falling back to ImageData. Your browser is likely old, \
 please upgrade."); }
 
 async function pushBuffer() {
-    bufptr = instance.exports.get_buffer();
+    bufptr = instance.exports.mn_get_buffer(renderer);
     buffer = new Uint8ClampedArray(instance.exports.memory.buffer, bufptr, BUFSIZE);
     imgData = new ImageData(buffer, WIDTH, HEIGHT);
     if (bmapSupport) {
@@ -62,9 +114,70 @@ async function pushBuffer() {
     }
 }
 
-// runs every frame, rotates model @ ROTRATE seconds per rotation
+// draws text into the frame buffer with the built-in font in src/font.rs
+const encoder = new TextEncoder();
+function drawText(x, y, text, color) {
+    const bytes = encoder.encode(text);
+    const ptr = instance.exports.mn_alloc(bytes.length);
+    new Uint8Array(instance.exports.memory.buffer, ptr, bytes.length).set(bytes);
+    instance.exports.mn_draw_text(renderer, x, y, ptr, bytes.length, color);
+    instance.exports.mn_free(ptr, bytes.length);
+}
+
+// converts a mouse position to frame buffer pixels, as the canvas can be shown bigger or smaller than it
+function toFrame(event) {
+    return {
+        x: event.offsetX * WIDTH / viewport.clientWidth,
+        y: event.offsetY * HEIGHT / viewport.clientHeight,
+    };
+}
+
+// the pixel under the mouse, for picking the triangle there with src/pick.rs
+let hover = null;
+// left drags turn the mesh, right and middle drags move it, and the wheel zooms, see src/camera.rs
+viewport.addEventListener('mousedown', function(event) {
+    const pos = toFrame(event);
+    instance.exports.mn_orbit_start_drag(renderer, event.button == 0 ? 0 : 1, pos.x, pos.y);
+    event.preventDefault();
+});
+viewport.addEventListener('mousemove', function(event) {
+    const pos = toFrame(event);
+    instance.exports.mn_orbit_drag_to(renderer, pos.x, pos.y);
+    hover = { x: Math.floor(pos.x), y: Math.floor(pos.y) };
+});
+window.addEventListener('mouseup', function() { instance.exports.mn_orbit_end_drag(renderer); });
+viewport.addEventListener('mouseleave', function() { hover = null; });
+viewport.addEventListener('contextmenu', function(event) { event.preventDefault(); });
+viewport.addEventListener('wheel', function(event) {
+    instance.exports.mn_orbit_zoom(renderer, -Math.sign(event.deltaY));
+    event.preventDefault();
+}, { passive: false });
+// space for the Hit mn_pick writes: the triangle index, then its barycentric weights and the hit point
+const HIT_SIZE = 28;
+const hitPtr = instance.exports.mn_alloc(HIT_SIZE);
+
+let showHud = true;
+let lastFrame = performance.now();
+let fps = 0;
+
+// runs every frame, the model spinning once every 5 seconds unless it's dragged or Space stopped it
 function onAnimFrame() {
-    instance.exports.render_spin(Date.now() / 1000, ROTRATE);
+    const now = performance.now();
+    instance.exports.mn_render_view(renderer, now / 1000);
+    let hoveredTri = null;
+    if (hover && instance.exports.mn_pick(renderer, hover.x, hover.y, hitPtr)) {
+        hoveredTri = new Uint32Array(instance.exports.memory.buffer, hitPtr, 1)[0];
+        instance.exports.mn_highlight_triangle(renderer, hoveredTri, 0xffc040);
+    }
+    // smoothed, so it's readable
+    const frameFps = 1000 / Math.max(now - lastFrame, 1);
+    fps = fps ? fps * 0.9 + frameFps * 0.1 : frameFps;
+    lastFrame = now;
+    if (showHud) {
+        const tris = instance.exports.mn_triangle_count(renderer);
+        const hovered = hoveredTri === null ? "" : `\ntri ${hoveredTri}`;
+        drawText(4, 4, `${MESH_NAMES[meshId]}\n${tris} tris\n${Math.round(fps)} fps${hovered}`, 0xffffff);
+    }
     pushBuffer();
     window.requestAnimationFrame(onAnimFrame);
 }
@@ -0,0 +1,447 @@
+//! Renders meshes to image files, with no window. Run with `--help` for the options.
+
+use std::error::Error;
+use std::f32::consts::TAU;
+
+use meenle_noonle::dither::{Dither, Palette};
+use meenle_noonle::effects::Effect;
+use meenle_noonle::fog::{Falloff, Fog};
+use meenle_noonle::gif::GifEncoder;
+use meenle_noonle::png::ApngEncoder;
+use meenle_noonle::scale::{Filter, ScaleMode, Scaler};
+use meenle_noonle::stereo::{Stereo, StereoMode};
+use meenle_noonle::svg::Svg;
+use meenle_noonle::{Axis, FrameBuffer, Mesh, Pixel, Renderer, Tri, Vec3};
+
+const USAGE: &str = "\
+usage: meenle-noonle-cli [options] --output <file>
+
+Renders a mesh to a .png, .ppm, .pam or .svg image. With --mode spin, writes a looping .gif or .apng, or one image
+per frame, numbered by replacing the #s in the output name (frame-####.png gives frame-0000.png,
+frame-0001.png...).
+
+options:
+  --mesh <name>         built-in mesh: monkey, icosphere or cube (default monkey)
+  --tris <file>         raw dump of #[repr(C)] Tri records, nine native-endian f32s each, instead of --mesh
+  --scale <n>           scale the mesh by n (default 1)
+  --rotate <x,y,z>      rotate the mesh about each axis, in degrees (default 0,0,0)
+  --offset <x,y>        move the mesh across the screen, in pixels (default 0,0)
+  --size <WxH>          size to render at (default 500x500)
+  --output-size <WxH>   scale images and animations to this size (default the --size)
+  --fit <mode>          how to scale: fit (with bars), stretch or integer (default fit)
+  --filter <filter>     scaling filter: nearest or bilinear (default bilinear)
+  --dither <algorithm>  dither images down to the --palette: bayer, floyd-steinberg or atkinson
+  --palette <palette>   colours to dither to: rgb565, mono, or grayN for N shades of grey (default rgb565)
+  --background <id>     demo background, 0 to 6 (default 0)
+  --effects <list>      effects to run, in order, separated by commas: bloom, scanlines, aberration,
+                        vignette, blur and trails
+  --stereo <mode>       draw the mesh for both eyes: anaglyph, side-by-side, top-bottom or cross-eye
+                        (not for .svg output)
+  --interocular <n>     distance between the eyes for --stereo, in pixels (default 40)
+  --line-width <n>      width of the mesh's edges, in pixels (default 1)
+  --fog <falloff>       fade edges with depth: linear or exponential (not for .svg output or with --stereo)
+  --fog-color <RRGGBB>  colour --fog fades toward (default the background)
+  --stroke <RRGGBB>     line colour for .svg output (default ffffff)
+  --hidden-lines        leave hidden lines out of .svg output
+  --mode <still|spin>   one image, or frames of the mesh spinning about the y axis (default still)
+  --fps <n>             spin frames per second (default 30)
+  --rotrate <seconds>   seconds per spin rotation (default 5)
+  --frames <n>          number of spin frames (default one whole rotation)
+  -o, --output <file>   where to write the image
+  -h, --help            show this message";
+
+enum Mode {
+    Still,
+    Spin,
+}
+
+struct Options {
+    mesh: Mesh,
+    scale: f32,
+    rotate: [f32; 3],
+    offset: [f32; 2],
+    size: (usize, usize),
+    output_size: Option<(usize, usize)>,
+    fit: ScaleMode,
+    filter: Filter,
+    dither: Option<Dither>,
+    palette: Palette,
+    background: u32,
+    effects: Vec<Effect>,
+    stereo: Option<StereoMode>,
+    interocular: f32,
+    line_width: f32,
+    fog: Option<Falloff>,
+    fog_color: Option<Pixel>,
+    stroke: Pixel,
+    hidden_lines: bool,
+    mode: Mode,
+    fps: f32,
+    rotrate: f32,
+    frames: Option<usize>,
+    output: String,
+}
+
+fn parse_list<const N: usize>(
+    arg: &str,
+    value: &str,
+    separator: char,
+) -> Result<[f32; N], Box<dyn Error>> {
+    let nums = value
+        .split(separator)
+        .map(|num| num.trim().parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("{arg}: {err}"))?;
+    nums.try_into()
+        .map_err(|_| format!("{arg} takes {N} numbers separated by '{separator}'").into())
+}
+
+/// Reads a raw dump of [Tri]s, as laid out in memory: nine f32s per triangle, three for each vertex.
+fn read_tris(path: &str) -> Result<Mesh, Box<dyn Error>> {
+    const TRI_SIZE: usize = std::mem::size_of::<Tri>();
+    let bytes = std::fs::read(path).map_err(|err| format!("{path}: {err}"))?;
+    if bytes.len() % TRI_SIZE != 0 {
+        return Err(
+            format!("{path}: size isn't a whole number of {TRI_SIZE} byte triangles").into(),
+        );
+    }
+    let tris = bytes
+        .chunks_exact(TRI_SIZE)
+        .map(|tri| {
+            let num =
+                |idx: usize| f32::from_ne_bytes(tri[idx * 4..idx * 4 + 4].try_into().unwrap());
+            let vert = |idx_vtx: usize| {
+                Vec3::from([num(idx_vtx * 3), num(idx_vtx * 3 + 1), num(idx_vtx * 3 + 2)])
+            };
+            Tri {
+                verts: [vert(0), vert(1), vert(2)],
+            }
+        })
+        .collect::<Vec<_>>();
+    Ok(Mesh::from(tris))
+}
+
+fn parse_args() -> Result<Options, Box<dyn Error>> {
+    let mut options = Options {
+        mesh: meenle_noonle::demo::mesh(0).unwrap(),
+        scale: 1.0,
+        rotate: [0.0; 3],
+        offset: [0.0; 2],
+        size: (meenle_noonle::WIDTH, meenle_noonle::HEIGHT),
+        output_size: None,
+        fit: ScaleMode::Fit,
+        filter: Filter::Bilinear,
+        dither: None,
+        palette: Palette::RGB565,
+        background: 0,
+        effects: Vec::new(),
+        stereo: None,
+        interocular: 40.0,
+        line_width: 1.0,
+        fog: None,
+        fog_color: None,
+        stroke: Pixel::from_hex(0xffffff),
+        hidden_lines: false,
+        mode: Mode::Still,
+        fps: 30.0,
+        rotrate: 5.0,
+        frames: None,
+        output: String::new(),
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            println!("{USAGE}");
+            std::process::exit(0);
+        }
+        if arg == "--hidden-lines" {
+            options.hidden_lines = true;
+            continue;
+        }
+        let value = args.next().ok_or_else(|| format!("{arg} needs a value"))?;
+        let number = |value: &str| value.parse::<f32>().map_err(|err| format!("{arg}: {err}"));
+        match arg.as_str() {
+            "--mesh" => {
+                let id = match value.as_str() {
+                    "monkey" => 0,
+                    "icosphere" => 1,
+                    "cube" => 2,
+                    _ => return Err(format!("unknown mesh {value}").into()),
+                };
+                options.mesh = meenle_noonle::demo::mesh(id).unwrap();
+            }
+            "--tris" => options.mesh = read_tris(&value)?,
+            "--scale" => options.scale = number(&value)?,
+            "--rotate" => options.rotate = parse_list(&arg, &value, ',')?,
+            "--offset" => options.offset = parse_list(&arg, &value, ',')?,
+            "--size" => {
+                let [width, height] = parse_list(&arg, &value, 'x')?;
+                options.size = (width as usize, height as usize);
+            }
+            "--output-size" => {
+                let [width, height] = parse_list(&arg, &value, 'x')?;
+                options.output_size = Some((width as usize, height as usize));
+            }
+            "--fit" => {
+                options.fit = match value.as_str() {
+                    "fit" => ScaleMode::Fit,
+                    "stretch" => ScaleMode::Stretch,
+                    "integer" => ScaleMode::Integer,
+                    _ => return Err(format!("unknown fit {value}").into()),
+                }
+            }
+            "--filter" => {
+                options.filter = match value.as_str() {
+                    "nearest" => Filter::Nearest,
+                    "bilinear" => Filter::Bilinear,
+                    _ => return Err(format!("unknown filter {value}").into()),
+                }
+            }
+            "--dither" => {
+                options.dither = Some(match value.as_str() {
+                    "bayer" => Dither::Bayer,
+                    "floyd-steinberg" => Dither::FloydSteinberg,
+                    "atkinson" => Dither::Atkinson,
+                    _ => return Err(format!("unknown dither {value}").into()),
+                })
+            }
+            "--palette" => {
+                options.palette = match value.as_str() {
+                    "rgb565" => Palette::RGB565,
+                    "mono" => Palette::MONO,
+                    _ => match value.strip_prefix("gray").map(str::parse::<u16>) {
+                        Some(Ok(shades)) if shades >= 2 => Palette::Gray(shades),
+                        _ => return Err(format!("unknown palette {value}").into()),
+                    },
+                }
+            }
+            "--background" => options.background = number(&value)? as u32,
+            "--effects" => {
+                for name in value.split(',') {
+                    let id = match name.trim() {
+                        "bloom" => 0,
+                        "scanlines" => 1,
+                        "aberration" => 2,
+                        "vignette" => 3,
+                        "blur" => 4,
+                        "trails" => 5,
+                        _ => return Err(format!("unknown effect {name}").into()),
+                    };
+                    options.effects.extend(Effect::demo(id));
+                }
+            }
+            "--stereo" => {
+                options.stereo = Some(match value.as_str() {
+                    "anaglyph" => StereoMode::Anaglyph,
+                    "side-by-side" => StereoMode::SideBySide,
+                    "top-bottom" => StereoMode::TopBottom,
+                    "cross-eye" => StereoMode::CrossEye,
+                    _ => return Err(format!("unknown stereo mode {value}").into()),
+                })
+            }
+            "--interocular" => options.interocular = number(&value)?,
+            "--line-width" => options.line_width = number(&value)?,
+            "--fog" => {
+                options.fog = Some(match value.as_str() {
+                    "linear" => Falloff::Linear,
+                    "exponential" => Falloff::Exponential,
+                    _ => return Err(format!("unknown fog falloff {value}").into()),
+                })
+            }
+            "--fog-color" => {
+                let hex = u32::from_str_radix(value.trim_start_matches('#'), 16)
+                    .map_err(|err| format!("{arg}: {err}"))?;
+                options.fog_color = Some(Pixel::from_hex(hex));
+            }
+            "--stroke" => {
+                let hex = u32::from_str_radix(value.trim_start_matches('#'), 16)
+                    .map_err(|err| format!("{arg}: {err}"))?;
+                options.stroke = Pixel::from_hex(hex);
+            }
+            "--mode" => {
+                options.mode = match value.as_str() {
+                    "still" => Mode::Still,
+                    "spin" => Mode::Spin,
+                    _ => return Err(format!("unknown mode {value}").into()),
+                }
+            }
+            "--fps" => options.fps = number(&value)?,
+            "--rotrate" => options.rotrate = number(&value)?,
+            "--frames" => options.frames = Some(number(&value)? as usize),
+            "-o" | "--output" => options.output = value,
+            _ => return Err(format!("unknown option {arg}\n\n{USAGE}").into()),
+        }
+    }
+    if options.output.is_empty() {
+        return Err(format!("no output file given\n\n{USAGE}").into());
+    }
+    if options.background >= meenle_noonle::demo::BACKGROUND_COUNT {
+        return Err(format!(
+            "there are only {} backgrounds",
+            meenle_noonle::demo::BACKGROUND_COUNT
+        )
+        .into());
+    }
+    if options.fps <= 0.0 || options.rotrate <= 0.0 {
+        return Err("--fps and --rotrate must be positive".into());
+    }
+    Ok(options)
+}
+
+/// The renderer's frame buffer, scaled to the --output-size if there is one, and dithered if asked.
+fn output_frame(renderer: &Renderer, options: &Options) -> FrameBuffer {
+    let frame_buffer = renderer.buffer();
+    let mut output = match options.output_size {
+        Some((width, height)) => {
+            let scaler = Scaler::new(
+                (frame_buffer.width(), frame_buffer.height()),
+                (width, height),
+                options.fit,
+                options.filter,
+            );
+            let mut scaled = FrameBuffer::new(width, height);
+            scaler.scale(frame_buffer, &mut scaled);
+            scaled
+        }
+        None => frame_buffer.clone(),
+    };
+    if let Some(dither) = options.dither {
+        meenle_noonle::dither::dither(&mut output, &options.palette, dither);
+    }
+    output
+}
+
+/// Saves the frame in the format the file extension asks for. Images are encoded from the renderer's frame
+/// buffer, while SVGs are drawn straight from the mesh.
+fn save(
+    renderer: &Renderer,
+    mesh: &Mesh,
+    options: &Options,
+    path: &str,
+) -> Result<(), Box<dyn Error>> {
+    let frame_buffer = &output_frame(renderer, options);
+    let bytes = match extension(path).as_deref() {
+        Some("svg") => {
+            let (width, height) = options.size;
+            let mut svg = Svg::new(width, height);
+            svg.stroke = options.stroke;
+            svg.stroke_width = options.line_width;
+            svg.hidden_line_removal = options.hidden_lines;
+            svg.draw_mesh(mesh);
+            svg.finish().into_bytes()
+        }
+        Some("png") => meenle_noonle::png::encode(frame_buffer),
+        Some("ppm") => meenle_noonle::ppm::encode_ppm(frame_buffer),
+        Some("pam") => meenle_noonle::ppm::encode_pam(frame_buffer),
+        _ => return Err(format!("{path}: can only write .png, .ppm, .pam or .svg").into()),
+    };
+    std::fs::write(path, bytes).map_err(|err| format!("{path}: {err}").into())
+}
+
+fn extension(path: &str) -> Option<String> {
+    path.rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase())
+}
+
+/// Where spin frames go.
+enum Animation {
+    Gif(GifEncoder),
+    Apng(ApngEncoder),
+    /// One image per frame.
+    Frames,
+}
+
+/// Replaces the run of #s in the output name with the zero-padded frame number.
+fn frame_path(pattern: &str, frame: usize) -> Option<String> {
+    let start = pattern.find('#')?;
+    let digits = pattern[start..]
+        .chars()
+        .take_while(|&chr| chr == '#')
+        .count();
+    Some(format!(
+        "{}{frame:0digits$}{}",
+        &pattern[..start],
+        &pattern[start + digits..]
+    ))
+}
+
+fn main() {
+    // errors are printed with Display rather than Debug, so the usage text comes out readable
+    if let Err(err) = run() {
+        eprintln!("meenle-noonle-cli: {err}");
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let options = parse_args()?;
+    let mut renderer = Renderer::with_size(options.size.0, options.size.1);
+    renderer.set_demo_background(options.background);
+    renderer.effects_mut().effects = options.effects.clone();
+    renderer.set_line_width(options.line_width);
+    renderer.set_stereo(options.stereo.map(|mode| Stereo {
+        interocular: options.interocular,
+        ..Stereo::new(mode)
+    }));
+    renderer.set_fog(options.fog.map(|falloff| Fog {
+        color: options.fog_color,
+        ..Fog::new(falloff)
+    }));
+
+    let mut mesh = options.mesh.clone();
+    mesh.scale(options.scale);
+    for (axis, degrees) in [Axis::X, Axis::Y, Axis::Z].into_iter().zip(options.rotate) {
+        mesh.rot(axis, degrees.to_radians());
+    }
+    let offset = Vec3::from([options.offset[0], options.offset[1], 0.0]);
+
+    match options.mode {
+        Mode::Still => {
+            mesh.translate(offset);
+            renderer.render_mesh(&mesh);
+            save(&renderer, &mesh, &options, &options.output)?;
+        }
+        Mode::Spin => {
+            let frames = options
+                .frames
+                .unwrap_or((options.fps * options.rotrate).round() as usize);
+            let (width, height) = options.output_size.unwrap_or(options.size);
+            let mut animation = match extension(&options.output).as_deref() {
+                Some("gif") => Animation::Gif(GifEncoder::new(width, height, options.fps)),
+                Some("apng") => Animation::Apng(ApngEncoder::new(width, height, options.fps)),
+                _ => Animation::Frames,
+            };
+            for frame in 0..frames {
+                // the same spin as Renderer::render_spin
+                let time_seconds = frame as f32 / options.fps;
+                let mut spun = mesh.clone();
+                spun.rot(Axis::Y, (time_seconds * TAU / options.rotrate) % TAU);
+                spun.translate(offset);
+                renderer.animate_background(time_seconds);
+                renderer.render_mesh(&spun);
+                match animation {
+                    Animation::Gif(ref mut gif) => {
+                        gif.add_frame(&output_frame(&renderer, &options))
+                    }
+                    Animation::Apng(ref mut apng) => {
+                        apng.add_frame(&output_frame(&renderer, &options))
+                    }
+                    Animation::Frames => {
+                        let path = frame_path(&options.output, frame).ok_or(
+                            "--mode spin needs a .gif or .apng output, or #s in the output name for the frame number",
+                        )?;
+                        save(&renderer, &spun, &options, &path)?;
+                    }
+                }
+            }
+            let bytes = match animation {
+                Animation::Gif(gif) => gif.finish(),
+                Animation::Apng(apng) => apng.finish(),
+                Animation::Frames => return Ok(()),
+            };
+            std::fs::write(&options.output, bytes)
+                .map_err(|err| format!("{}: {err}", options.output))?;
+        }
+    }
+    Ok(())
+}
@@ -1,13 +1,72 @@
+//! Interactive viewer for the built-in meshes and model files. Run with `--help` for the options.
+
 use std::error::Error;
+use std::f32::consts::TAU;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 
+use meenle_noonle::camera::Drag;
+use meenle_noonle::effects::Effect;
+use meenle_noonle::fog::{Falloff, Fog};
+use meenle_noonle::scale::{Filter, ScaleMode, Scaler};
+use meenle_noonle::stereo::{Stereo, StereoMode};
+use meenle_noonle::{Axis, Mesh};
 use sdl2::keyboard::Keycode;
+use sdl2::mouse::{MouseButton, MouseWheelDirection};
 
 const MN_PXL_FMT: sdl2::pixels::PixelFormatEnum = sdl2::pixels::PixelFormatEnum::ABGR8888; // RGBA
 
+const USAGE: &str = "\
+Usage: meenle_noonle_sdl [options] [model]
+
+Shows a .obj or .stl model, or the built-in meshes without one. Models can also be dropped onto the window.
+
+Options:
+  --watch     reload the model whenever it changes on disk
+  -h, --help  show this message";
+
+/// Size of the longest side of a model once it's loaded, in pixels.
+const MODEL_SIZE: f32 = 200.0;
+/// How often --watch looks at the model's modification time.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A model file being shown, or that was asked for but didn't load.
+struct Model {
+    path: PathBuf,
+    /// When the file was last changed, as of when it last loaded. [None] if it didn't load, so --watch keeps
+    /// trying it.
+    modified: Option<SystemTime>,
+    /// Why it didn't load the last time it was tried, if it didn't.
+    error: Option<String>,
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
+    let mut watch = false;
+    let mut model_path = None;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--watch" => watch = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(());
+            }
+            _ if arg.starts_with('-') => {
+                return Err(format!("unknown option {arg}\n\n{USAGE}").into())
+            }
+            _ => model_path = Some(PathBuf::from(arg)),
+        }
+    }
+
     let mut mesh_idx = 0;
-    meenle_noonle::generate_background();
-    meenle_noonle::demo::set_mesh(mesh_idx);
+    let mut background_idx = 0;
+    let mut stereo_idx: u32 = 0;
+    let mut fog_idx: u32 = 0;
+    let mut show_hud = true;
+    let mut renderer = meenle_noonle::Renderer::new();
+    renderer.generate_background();
+    renderer.set_mesh(mesh_idx);
+    // for the HUD. A model that doesn't load leaves the last mesh on screen, so this keeps that one's name
+    let mut mesh_name = demo_mesh_name(mesh_idx);
 
     let sdl = sdl2::init()?;
     let video = sdl.video()?;
@@ -19,15 +78,22 @@ fn main() -> Result<(), Box<dyn Error>> {
             meenle_noonle::HEIGHT as u32,
         )
         .position_centered()
+        .resizable()
         .opengl()
         .allow_highdpi()
         .build()?;
     let mut canvas = window.into_canvas().present_vsync().build()?;
 
-    let mn_fb = meenle_noonle::get_buffer();
-    // coerce mn_fb to slice from 1M char array
-    let mn_fb: *mut [u8; 1_000_000] = unsafe { std::mem::transmute(mn_fb.as_ptr()) };
-    let mn_fb = &unsafe { &mut *mn_fb }[..];
+    let mut model = None;
+    if let Some(path) = model_path {
+        model = Some(open_model(
+            path,
+            &mut renderer,
+            canvas.window_mut(),
+            &mut mesh_name,
+        )?);
+    }
+    let mut last_watch = Instant::now();
 
     let texture_creator = canvas.texture_creator();
     let mut mn_texture = texture_creator.create_texture_streaming(
@@ -36,41 +102,301 @@ fn main() -> Result<(), Box<dyn Error>> {
         meenle_noonle::HEIGHT as u32,
     )?;
 
+    let start = Instant::now();
+    let mut last_frame = start;
+    let mut fps = 0.0;
+
     // every frame
     'main_loop: loop {
-        let epoch = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)?
-            .as_secs_f64();
-        
-        meenle_noonle::demo::render_spin(epoch, 5.0);
-        mn_texture.with_lock(None, |buf, _| buf.copy_from_slice(mn_fb))?;
-        canvas.copy(&mn_texture, None, None)?;
-        
+        // render at the window's size in pixels, which high-DPI screens have more of than the window has units
+        let (drawable_width, drawable_height) = canvas.output_size()?;
+        let (width, height) = (drawable_width.max(1), drawable_height.max(1));
+        if (width as usize, height as usize)
+            != (renderer.buffer().width(), renderer.buffer().height())
+        {
+            renderer.resize(width as usize, height as usize);
+            renderer.view_mut().pixel_ratio = width as f32 / canvas.window().size().0.max(1) as f32;
+            mn_texture = texture_creator.create_texture_streaming(MN_PXL_FMT, width, height)?;
+        }
+
+        if let Some(shown) = model.as_mut().filter(|_| watch) {
+            if last_watch.elapsed() >= WATCH_INTERVAL {
+                last_watch = Instant::now();
+                // editors can briefly remove the file while saving it, so only reload once it's back
+                let modified = modification_time(&shown.path);
+                if modified.is_some() && modified != shown.modified {
+                    // if it's broken, the last version that loaded stays up until it's fixed. Modification times
+                    // can be too coarse to tell a half-written file from the finished one, so broken ones are
+                    // tried again until they load
+                    shown.error = show_model(
+                        &shown.path,
+                        &mut renderer,
+                        canvas.window_mut(),
+                        &mut mesh_name,
+                    )?;
+                    shown.modified = modified.filter(|_| shown.error.is_none());
+                }
+            }
+        }
+
+        renderer.render_view(start.elapsed().as_secs_f32());
+
+        // smoothed, so it's readable
+        let now = Instant::now();
+        let frame_fps = 1.0 / (now - last_frame).as_secs_f32().max(1e-6);
+        fps = if fps == 0.0 {
+            frame_fps
+        } else {
+            fps * 0.9 + frame_fps * 0.1
+        };
+        last_frame = now;
+        if show_hud {
+            let tris = renderer.demo_mesh().map_or(0, |mesh| mesh.tris.len());
+            let mut hud = format!("{mesh_name}\n{tris} tris\n{fps:.0} fps");
+            if let Some(Model {
+                path,
+                error: Some(err),
+                ..
+            }) = &model
+            {
+                hud += &format!("\n{}: {err}", file_name(path));
+            }
+            renderer.draw_text(4, 4, &hud, meenle_noonle::Pixel::rgb(255, 255, 255));
+        }
+
+        // only upload the rows that changed
+        if let Some(dirty) = renderer.dirty_rect() {
+            let frame_buffer = renderer.buffer();
+            let rows = dirty.rows();
+            let pitch = frame_buffer.stride();
+            let rect = sdl2::rect::Rect::new(
+                0,
+                rows.start as i32,
+                frame_buffer.width() as u32,
+                rows.len() as u32,
+            );
+            let bytes = &frame_buffer.bytes()[rows.start * pitch..rows.end * pitch];
+            mn_texture.update(rect, bytes, pitch)?;
+        }
+        // the frame buffer follows the window, but keep the picture's shape in case they're out of step, with bars
+        // around it
+        let picture = Scaler::new(
+            (renderer.buffer().width(), renderer.buffer().height()),
+            (drawable_width as usize, drawable_height as usize),
+            ScaleMode::Fit,
+            Filter::Nearest,
+        )
+        .picture();
+        // mouse positions are in the window's units
+        let dpi_scale = renderer.view().pixel_ratio;
+        let frame_scale = renderer.buffer().width() as f32 / picture.width.max(1) as f32;
+        let to_frame = |x: i32, y: i32| {
+            (
+                (x as f32 * dpi_scale - picture.x as f32) * frame_scale,
+                (y as f32 * dpi_scale - picture.y as f32) * frame_scale,
+            )
+        };
+        canvas.clear();
+        canvas.copy(
+            &mn_texture,
+            None,
+            sdl2::rect::Rect::new(
+                picture.x as i32,
+                picture.y as i32,
+                picture.width,
+                picture.height,
+            ),
+        )?;
+
         for event in event_pump.poll_iter() {
             match event {
                 sdl2::event::Event::Quit { .. } => break 'main_loop,
+                sdl2::event::Event::DropFile { filename, .. } => {
+                    let path = PathBuf::from(filename);
+                    model = Some(open_model(
+                        path,
+                        &mut renderer,
+                        canvas.window_mut(),
+                        &mut mesh_name,
+                    )?);
+                }
+                // left drags turn the mesh, right and middle drags move it, and scrolling zooms
+                sdl2::event::Event::MouseButtonDown {
+                    mouse_btn, x, y, ..
+                } => {
+                    let drag = match mouse_btn {
+                        MouseButton::Left => Drag::Orbit,
+                        MouseButton::Right | MouseButton::Middle => Drag::Pan,
+                        _ => continue,
+                    };
+                    let (x, y) = to_frame(x, y);
+                    renderer.view_mut().start_drag(drag, x, y);
+                }
+                sdl2::event::Event::MouseMotion { x, y, .. } => {
+                    let (x, y) = to_frame(x, y);
+                    renderer.view_mut().drag_to(x, y);
+                }
+                sdl2::event::Event::MouseButtonUp { .. } => renderer.view_mut().end_drag(),
+                sdl2::event::Event::MouseWheel { y, direction, .. } => {
+                    let steps = match direction {
+                        MouseWheelDirection::Flipped => -y,
+                        _ => y,
+                    };
+                    renderer.view_mut().zoom(steps);
+                }
                 sdl2::event::Event::KeyDown {
                     keycode: Some(keycode),
                     ..
                 } => match keycode {
-                    Keycode::Up => {
-                        mesh_idx += 1;
-                        meenle_noonle::demo::scale_mesh_to_screen(0.0);
-                        meenle_noonle::demo::set_mesh(mesh_idx);
+                    // the built-in meshes, going round from the last back to the first
+                    Keycode::Up | Keycode::Down => {
+                        let count = meenle_noonle::demo::MESH_COUNT;
+                        mesh_idx = match keycode {
+                            Keycode::Up => (mesh_idx + 1) % count,
+                            _ => (mesh_idx + count - 1) % count,
+                        };
+                        renderer.set_mesh(mesh_idx);
+                        mesh_name = demo_mesh_name(mesh_idx);
+                        model = None;
+                        canvas.window_mut().set_title("Meenle-Noonle")?;
                     }
-                    Keycode::Down => {
-                        mesh_idx -= 1;
-                        meenle_noonle::demo::scale_mesh_to_screen(0.0);
-                        meenle_noonle::demo::set_mesh(mesh_idx);
+                    Keycode::B => {
+                        background_idx =
+                            (background_idx + 1) % meenle_noonle::demo::BACKGROUND_COUNT;
+                        renderer.set_demo_background(background_idx);
+                    }
+                    // a screenshot that can't be written isn't worth closing the viewer over
+                    Keycode::S => {
+                        if let Err(err) = save_screenshot(renderer.buffer()) {
+                            eprintln!("couldn't save screenshot: {err}");
+                        }
+                    }
+                    Keycode::H => show_hud = !show_hud,
+                    Keycode::Space => {
+                        let view = renderer.view_mut();
+                        view.auto_spin = !view.auto_spin;
+                    }
+                    Keycode::R => renderer.view_mut().reset(),
+                    // [ and ] make the lines thinner and thicker
+                    Keycode::LeftBracket => {
+                        renderer.set_line_width((renderer.line_width() - 1.0).max(1.0))
+                    }
+                    Keycode::RightBracket => {
+                        renderer.set_line_width((renderer.line_width() + 1.0).min(8.0))
+                    }
+                    // off, then each stereo mode in turn
+                    Keycode::D => {
+                        stereo_idx = (stereo_idx + 1) % 5;
+                        let mode = StereoMode::from_id(stereo_idx.wrapping_sub(1));
+                        renderer.set_stereo(mode.map(Stereo::new));
+                    }
+                    // off, linear, then exponential fog
+                    Keycode::F => {
+                        fog_idx = (fog_idx + 1) % 3;
+                        let falloff = Falloff::from_id(fog_idx.wrapping_sub(1));
+                        renderer.set_fog(falloff.map(Fog::new));
+                    }
+                    // 1 to 6 toggle the effects
+                    Keycode::Num1
+                    | Keycode::Num2
+                    | Keycode::Num3
+                    | Keycode::Num4
+                    | Keycode::Num5
+                    | Keycode::Num6 => {
+                        let id = keycode as i32 - Keycode::Num1 as i32;
+                        if let Some(effect) = Effect::demo(id as u32) {
+                            renderer.effects_mut().toggle(effect);
+                        }
                     }
                     _ => (),
                 },
                 _ => (),
             }
         }
-        
+
         canvas.present();
     }
 
     Ok(())
 }
+
+/// Loads a model file, sized and turned the right way up for the screen like the built-in meshes.
+fn load_model(path: &Path) -> Result<Mesh, Box<dyn Error>> {
+    let bytes = std::fs::read(path)?;
+    let mut mesh = meenle_noonle::model::load(&path.to_string_lossy(), &bytes)?;
+    // model files have y going up, and the screen has it going down
+    mesh.rot(Axis::X, TAU / 2.0);
+    mesh.fit(MODEL_SIZE);
+    Ok(mesh)
+}
+
+/// Shows the model in place of the demo mesh, with its name in the window title and as `mesh_name`. If it can't
+/// be loaded, the error goes in the title instead and the mesh and `mesh_name` are left as they were. Returns
+/// the error if it didn't load.
+fn show_model(
+    path: &Path,
+    renderer: &mut meenle_noonle::Renderer,
+    window: &mut sdl2::video::Window,
+    mesh_name: &mut String,
+) -> Result<Option<String>, Box<dyn Error>> {
+    match load_model(path) {
+        Ok(mesh) => {
+            renderer.set_demo_mesh(mesh);
+            *mesh_name = file_name(path);
+            window.set_title(&format!("Meenle-Noonle - {}", path.display()))?;
+            Ok(None)
+        }
+        Err(err) => {
+            window.set_title(&format!("Meenle-Noonle - {}: {err}", path.display()))?;
+            Ok(Some(err.to_string()))
+        }
+    }
+}
+
+/// Shows the model, see [show_model], and keeps track of it whether it loaded or not, so --watch picks it up
+/// once it's fixed.
+fn open_model(
+    path: PathBuf,
+    renderer: &mut meenle_noonle::Renderer,
+    window: &mut sdl2::video::Window,
+    mesh_name: &mut String,
+) -> Result<Model, Box<dyn Error>> {
+    let modified = modification_time(&path);
+    let error = show_model(&path, renderer, window, mesh_name)?;
+    Ok(Model {
+        path,
+        modified: modified.filter(|_| error.is_none()),
+        error,
+    })
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn demo_mesh_name(mesh_idx: u32) -> String {
+    meenle_noonle::demo::MESH_NAMES
+        .get(mesh_idx as usize)
+        .unwrap_or(&"?")
+        .to_string()
+}
+
+fn modification_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Saves the frame buffer as a PNG in the working directory, named after the current time.
+fn save_screenshot(frame_buffer: &meenle_noonle::FrameBuffer) -> Result<(), Box<dyn Error>> {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+    let path = format!("meenle-noonle-{secs}.png");
+    std::fs::write(&path, meenle_noonle::png::encode(frame_buffer))?;
+    println!("saved {path}");
+    Ok(())
+}
@@ -3,27 +3,43 @@
 
 #include <revolution.h> // Wii libraries
 #include <math.h> // Trig functions for 3D
+#include <stdio.h> // sprintf for the HUD
 #include <string>
 
-// const u32 RUST_HEAP_SIZE = 0x400000; // 4 MiB
-const u32 RUST_HEAP_SIZE = 0x001000; // 4 KiB
+const u32 RUST_HEAP_SIZE = 0x400000; // 4 MiB, the renderer's frame buffers live on the rust heap
 const u32 MNFB_WIDTH = 500; // MNFB = Meenle-Noonle Frame Buffer
 const f64 TAU =  3.14159265358979323846 * 2;
 const f64 ROTRATE = 5; // seconds per rotation
+const u32 MESH_COUNT = 4; // monkey, icosphere, cube, tentacle
+const u32 MESH_SECONDS = 10; // how long each mesh is shown for
+const char* const MESH_NAMES[MESH_COUNT] = {"monkey", "icosphere", "cube", "tentacle"};
+const u32 BACKGROUND_COUNT = 7; // classic, solid, linear, radial, checkerboard, starfield, plasma
 
 const GXColor black = {0, 0, 0, 255};
 const GXColor white = {255, 255, 255, 255};
+const u32 BAR_COLOR = 0x0b0b44; // pillarbox either side of the MNFB
+const f32 LINE_WIDTH = 2; // one pixel lines flicker on interlaced TVs
 
 struct ColorRGBA { u8 r; u8 g; u8 b; u8 a; };
-struct ColorYUV { u8 Y; u8 U; u8 V; };
+struct MNRect { u32 x; u32 y; u32 width; u32 height; };
 typedef u32 ColorYUYV; // 0xYYUUYYVV; YUYV color format encodes two pixels in four bytes
 
 // Functions from rust
+struct Renderer; // opaque
 extern "C" { 
-    void generate_background();
-    void render(f64 scalar, f64 x_angle, f64 y_angle, f64 z_angle);
-    ColorRGBA* get_buffer();
-    void drawMNFBIntoXFB(void* mnfb, void* xfb, u32 fb_width);
+    Renderer* mn_create();
+    void mn_generate_background(Renderer* renderer);
+    void mn_set_mesh(Renderer* renderer, u32 id);
+    void mn_set_demo_background(Renderer* renderer, u32 id);
+    void mn_render_spin(Renderer* renderer, f32 time_seconds, f32 rotrate);
+    void mn_set_line_width(Renderer* renderer, f32 width);
+    const ColorRGBA* mn_get_buffer(const Renderer* renderer);
+    bool mn_get_dirty_rect(const Renderer* renderer, MNRect* rect);
+    void mn_draw_text(Renderer* renderer, s32 x, s32 y, const char* text, u32 len, u32 color);
+    u32 mn_triangle_count(const Renderer* renderer);
+    ColorYUYV mn_rgb_to_yuyv(u32 color1, u32 color2);
+    void mn_draw_into_xfb(const Renderer* renderer, void* xfb, u32 xfb_width, u32 xfb_height, u32 bar_color,
+                          u32 row_start, u32 row_end);
 }
 
 void setupRustHeap() {
@@ -45,29 +61,9 @@ void setupVI(const GXRenderModeObj &rmode, const u32 xfbSize, void* &xfb1, void*
     VIWaitForRetrace();
 }
 
-ColorYUV RGBAtoYUV(const ColorRGBA color) {
-    u8 Ychar, Uchar, Vchar;
-    f32 Y =  0.257 * color.r + 0.504 * color.g + 0.098 * color.b +  16;
-    f32 U = -0.148 * color.r - 0.291 * color.g + 0.439 * color.b + 128;
-    f32 V =  0.439 * color.r - 0.368 * color.g - 0.071 * color.b + 128;
-    OSf32tou8(&Y, &Ychar);
-    OSf32tou8(&U, &Uchar);
-    OSf32tou8(&V, &Vchar);
-    ColorYUV ret = {Ychar, Uchar, Vchar};
-    return ret;
-}
-
-// TODO: for best color output, calculate from three colors: i-1, i, i+1
-extern "C" ColorYUYV RGBAtoYUYV(const ColorRGBA color1, const ColorRGBA color2) {
-    ColorYUV YUV1 = RGBAtoYUV(color1);
-    ColorYUV YUV2 = RGBAtoYUV(color2);
-    u8 U = 0.5 * YUV1.U + 0.5 * YUV2.U;
-    u8 V = 0.5 * YUV1.V + 0.5 * YUV2.V;
-    return (YUV1.Y << 24) + (U << 16) + (YUV2.Y << 8) + V;
-}
-
 void fillFBWithColor(void* xfb, u32 &xfbSize, ColorRGBA RGBA) {
-    u32 color = RGBAtoYUYV(RGBA, RGBA);
+    u32 hex = (RGBA.r << 16) | (RGBA.g << 8) | RGBA.b;
+    ColorYUYV color = mn_rgb_to_yuyv(hex, hex);
     u32 pxl = 0;
     u8* fb = (u8*) xfb;
     for (u8* ptr = fb; ptr < fb + xfbSize; ptr += VI_DISPLAY_PIX_SZ * 2) {
@@ -85,18 +81,47 @@ void main() {
     void *xfb1, *xfb2;
     setupVI(rmode, xfbSize, xfb1, xfb2);
 
-    generate_background();
-    ColorRGBA* mnfb = get_buffer(); // meenle-noonle frame buffer
-    render(0, 0, 0, 0);
+    Renderer* renderer = mn_create();
+    mn_generate_background(renderer);
+    mn_set_line_width(renderer, LINE_WIDTH);
 
     u32 frameNum;
+    u32 meshId = MESH_COUNT; // none yet
+    // rows of the MNFB that changed the frame before. Both XFBs start out empty, so everything
+    u32 prevRowStart = 0, prevRowEnd = MNFB_WIDTH;
     while (1) {
         frameNum = VIGetRetraceCount();
         void* xfbNext = (VIGetCurrentFrameBuffer() == xfb2? xfb1: xfb2);
         VISetNextFrameBuffer(xfbNext);
 
-        render(2, TAU / 2, ((frameNum % 3600 ) / 60.0 * TAU / ROTRATE), 0);
-        drawMNFBIntoXFB(mnfb, xfbNext, rmode.fbWidth);
+        u32 nextMeshId = frameNum / (60 * MESH_SECONDS) % MESH_COUNT;
+        if (nextMeshId != meshId) {
+            meshId = nextMeshId;
+            mn_set_mesh(renderer, meshId);
+            // and a new background with every mesh
+            mn_set_demo_background(renderer, frameNum / (60 * MESH_SECONDS) % BACKGROUND_COUNT);
+        }
+        mn_render_spin(renderer, (frameNum % 3600) / 60.0, ROTRATE);
+
+        // HUD in the top left corner, in the library's built-in font
+        char hud[64];
+        int hudLen = sprintf(hud, "%s\n%lu tris", MESH_NAMES[meshId], (unsigned long) mn_triangle_count(renderer));
+        mn_draw_text(renderer, 4, 4, hud, hudLen, 0xffffff);
+
+        // each XFB was last drawn two frames ago, so it needs the rows changed this frame and the frame before
+        MNRect dirty;
+        u32 rowStart = MNFB_WIDTH, rowEnd = 0;
+        if (mn_get_dirty_rect(renderer, &dirty)) {
+            rowStart = dirty.y;
+            rowEnd = dirty.y + dirty.height;
+        }
+        u32 xfbRowStart = rowStart < prevRowStart ? rowStart : prevRowStart;
+        u32 xfbRowEnd = rowEnd > prevRowEnd ? rowEnd : prevRowEnd;
+        if (xfbRowStart < xfbRowEnd) {
+            mn_draw_into_xfb(renderer, xfbNext, rmode.fbWidth, rmode.xfbHeight, BAR_COLOR, xfbRowStart, xfbRowEnd);
+        }
+        prevRowStart = rowStart;
+        prevRowEnd = rowEnd;
 
         VIFlush();
         VIWaitForRetrace();
@@ -1,9 +1,6 @@
 #![no_std]
 #![feature(panic_info_message)]
 
-const MNFB_WIDTH: u32 = 500;
-type ColorYUYV = u32; // 0xYYUUYYVV; YUYV color format encodes two pixels in four bytes
-
 #[global_allocator]
 static RUSTALLOC: RustAlloc = RustAlloc {};
 extern crate alloc;
@@ -19,49 +16,18 @@ extern "C" {
     fn OSAllocFromHeap(heap: i32, size: u32) -> *mut u8;
     fn OSFreeToHeap(heap: i32, ptr: *mut u8);
     fn OSPanic(file: *const c_char, line: i32, msg: *const c_char, ...) -> !;
-    fn RGBAtoYUYV(color1: ColorRGBA, color2: ColorRGBA) -> ColorYUYV;
     fn OSReport(msg: *const c_char, ...);
     fn sin(n: f64) -> f64;
     fn cos(n: f64) -> f64;
-}
-
-#[repr(C)]
-#[rustfmt::skip]
-#[derive(Clone, Copy)]
-pub struct ColorRGBA { r: u8, g: u8, b: u8, a: u8, }
-
-#[no_mangle]
-#[allow(non_snake_case)]
-pub unsafe extern "C" fn drawMNFBIntoXFB(
-    mnfb: *const [[ColorRGBA; MNFB_WIDTH as usize]; MNFB_WIDTH as usize],
-    xfb: *mut [[ColorYUYV; 320]; 480], // 640 x 480
-    fb_width: u32,
-) {
-    let bg_color = ColorRGBA {
-        r: 11,
-        g: 11,
-        b: 68,
-        a: 255,
-    };
-    let bg_color: ColorYUYV = RGBAtoYUYV(bg_color, bg_color);
-    let vbar_width: ColorYUYV = (fb_width - MNFB_WIDTH) / 4; // = 35; Pillarboxing
-    for (idx_row, row) in (*xfb).iter_mut().enumerate() {
-        for (idx_col, yuyv) in row.iter_mut().enumerate() {
-            if idx_col as u32 <= vbar_width || idx_col as u32 >= vbar_width + MNFB_WIDTH / 2 {
-                *yuyv = bg_color;
-            } else {
-                let mn_col = (idx_col - vbar_width as usize) * 2;
-                let color1: ColorRGBA = (*mnfb)[idx_row][mn_col];
-                let color2: ColorRGBA = (*mnfb)[idx_row + 1][mn_col];
-                *yuyv = RGBAtoYUYV(color1, color2);
-            }
-        }
-    }
+    fn sqrt(n: f64) -> f64;
+    fn exp(n: f64) -> f64;
 }
 
 pub trait MSLmaths {
     fn sin(self) -> Self;
     fn cos(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn exp(self) -> Self;
 }
 impl MSLmaths for f64 {
     fn sin(self) -> Self {
@@ -70,6 +36,26 @@ impl MSLmaths for f64 {
     fn cos(self) -> Self {
         unsafe { cos(self) }
     }
+    fn sqrt(self) -> Self {
+        unsafe { sqrt(self) }
+    }
+    fn exp(self) -> Self {
+        unsafe { exp(self) }
+    }
+}
+impl MSLmaths for f32 {
+    fn sin(self) -> Self {
+        unsafe { sin(self as f64) as f32 }
+    }
+    fn cos(self) -> Self {
+        unsafe { cos(self as f64) as f32 }
+    }
+    fn sqrt(self) -> Self {
+        unsafe { sqrt(self as f64) as f32 }
+    }
+    fn exp(self) -> Self {
+        unsafe { exp(self as f64) as f32 }
+    }
 }
 
 struct RustAlloc {}
@@ -0,0 +1,333 @@
+//! Backgrounds the frame buffer is cleared to. Pick one with [Renderer::set_background]; animated ones, like
+//! [Background::Plasma], are redrawn every frame by [Renderer::render_spin].
+
+#[cfg(target_arch = "powerpc")]
+use rs_ppc_support::MSLmaths;
+
+use crate::dither::{self, Dither};
+use crate::*;
+
+#[derive(Debug, Clone, Default)]
+pub enum Background {
+    /// The pretty red, green and blue gradient.
+    #[default]
+    Classic,
+    Solid(Pixel),
+    /// Gradient from one colour to the other, running across the screen at `angle` radians from the x axis.
+    LinearGradient {
+        from: Pixel,
+        to: Pixel,
+        angle: f32,
+    },
+    /// Gradient from the centre of the screen out to its corners.
+    RadialGradient {
+        inner: Pixel,
+        outer: Pixel,
+    },
+    Checkerboard {
+        color1: Pixel,
+        color2: Pixel,
+        size: u32,
+    },
+    /// Stars of random brightness scattered over the sky colour. The same seed always gives the same stars, and
+    /// different seeds different ones.
+    Starfield {
+        sky: Pixel,
+        seed: u32,
+    },
+    /// A user-supplied image, stretched to fill the screen. An empty image shows [Background::Classic] instead.
+    Image(FrameBuffer),
+    /// Animated demoscene plasma.
+    Plasma,
+}
+
+impl Background {
+    /// Whether the background changes over time, and needs redrawing every frame.
+    pub fn is_animated(&self) -> bool {
+        matches!(self, Background::Plasma)
+    }
+
+    /// Draws the background, as it looks at `time_seconds`, over the whole frame buffer.
+    pub fn draw<F: PixelFormat>(&self, frame_buffer: &mut FrameBuffer<F>, time_seconds: f32) {
+        self.draw_dithered(frame_buffer, time_seconds, None);
+    }
+
+    /// Like [Background::draw], dithered down to the colours the frame buffer's format stores, if it has a
+    /// [PixelFormat::palette].
+    pub fn draw_dithered<F: PixelFormat>(
+        &self,
+        frame_buffer: &mut FrameBuffer<F>,
+        time_seconds: f32,
+        dither: Option<Dither>,
+    ) {
+        let (width, height) = (frame_buffer.width, frame_buffer.height);
+        // backgrounds are worked out in RGBA, then packed into the frame buffer's format
+        let mut pixels = vec![Pixel::default(); width * height];
+        self.draw_pixels(&mut pixels, width, height, time_seconds);
+        if let (Some(dither), Some(palette)) = (dither, F::palette()) {
+            dither::dither_pixels(&mut pixels, width, &palette, dither);
+        }
+        let stride = frame_buffer.stride();
+        for (row, bytes) in pixels
+            .chunks_exact(width.max(1))
+            .zip(frame_buffer.bytes.chunks_exact_mut(stride.max(1)))
+        {
+            F::encode_row(row, bytes);
+        }
+    }
+
+    fn draw_pixels(&self, pixels: &mut [Pixel], width: usize, height: usize, time_seconds: f32) {
+        let rows = pixels.chunks_exact_mut(width.max(1));
+        match self {
+            Background::Classic => {
+                for (idx_row, row) in rows.enumerate() {
+                    for (idx_col, pxl) in row.iter_mut().enumerate() {
+                        *pxl = Pixel {
+                            r: ((255.0 / height as f32) * idx_row as f32) as u8,
+                            g: ((255.0 / width as f32) * idx_col as f32) as u8,
+                            b: ((-(255.0 / height as f32) * idx_row as f32) + 255.0) as u8,
+                            a: 255,
+                        };
+                    }
+                }
+            }
+            Background::Solid(color) => pixels.fill(*color),
+            Background::LinearGradient { from, to, angle } => {
+                // project every pixel onto the gradient's direction, then stretch that to cover the screen
+                let (dir_x, dir_y) = (angle.cos(), angle.sin());
+                let reach = (dir_x * width as f32).abs() + (dir_y * height as f32).abs();
+                let start = (dir_x * width as f32).min(0.0) + (dir_y * height as f32).min(0.0);
+                for (idx_row, row) in rows.enumerate() {
+                    for (idx_col, pxl) in row.iter_mut().enumerate() {
+                        let along = dir_x * idx_col as f32 + dir_y * idx_row as f32 - start;
+                        *pxl = from.lerp(*to, along / reach.max(1.0));
+                    }
+                }
+            }
+            Background::RadialGradient { inner, outer } => {
+                let (centre_x, centre_y) = (width as f32 / 2.0, height as f32 / 2.0);
+                let radius = (centre_x * centre_x + centre_y * centre_y).sqrt().max(1.0);
+                for (idx_row, row) in rows.enumerate() {
+                    let dy = idx_row as f32 - centre_y;
+                    for (idx_col, pxl) in row.iter_mut().enumerate() {
+                        let dx = idx_col as f32 - centre_x;
+                        *pxl = inner.lerp(*outer, (dx * dx + dy * dy).sqrt() / radius);
+                    }
+                }
+            }
+            Background::Checkerboard {
+                color1,
+                color2,
+                size,
+            } => {
+                let size = (*size as usize).max(1);
+                for (idx_row, row) in rows.enumerate() {
+                    for (idx_col, pxl) in row.iter_mut().enumerate() {
+                        let odd = (idx_row / size + idx_col / size) % 2 == 1;
+                        *pxl = if odd { *color2 } else { *color1 };
+                    }
+                }
+            }
+            Background::Starfield { sky, seed } => {
+                pixels.fill(*sky);
+                // xorshift, so the stars are the same every time without needing a random number generator. It
+                // can't start from 0, so the seed goes in the top half of a 64 bit state that's never 0, which
+                // keeps every seed's stars its own
+                let mut state = (u64::from(*seed) << 32) | 0x9e37_79b9;
+                let mut random = || {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    (state >> 32) as u32
+                };
+                for _ in 0..width * height / 300 {
+                    let idx = random() as usize % pixels.len().max(1);
+                    let brightness = (random() % 200) as f32 / 255.0 + 0.2;
+                    pixels[idx] = sky.lerp(Pixel::WHITE, brightness);
+                }
+            }
+            Background::Image(image) => {
+                if image.width == 0 || image.height == 0 {
+                    Background::Classic.draw_pixels(pixels, width, height, time_seconds);
+                    return;
+                }
+                for (idx_row, row) in rows.enumerate() {
+                    let image_row = idx_row * image.height / height;
+                    for (idx_col, pxl) in row.iter_mut().enumerate() {
+                        *pxl =
+                            image.pixels()[image_row * image.width + idx_col * image.width / width];
+                    }
+                }
+            }
+            Background::Plasma => {
+                // the sum of a few sine waves, each only depending on x, y or x + y, so they're worked out
+                // once per row or column rather than once per pixel
+                let time = time_seconds;
+                let wave = |len: usize, freq: f32, speed: f32| -> Vec<f32> {
+                    (0..len)
+                        .map(|idx| (idx as f32 * freq + time * speed).sin())
+                        .collect()
+                };
+                let cols = wave(width, 0.031, 1.0);
+                let row_waves = wave(height, 0.023, 1.3);
+                let diagonals = wave(width + height, 0.017, 0.7);
+                let palette: Vec<Pixel> = (0..256)
+                    .map(|idx| {
+                        let phase = idx as f32 / 256.0 * core::f32::consts::TAU;
+                        Pixel::rgb(
+                            (127.0 + 127.0 * (phase + time * 0.5).sin()) as u8,
+                            (127.0 + 127.0 * (phase + 2.1).sin()) as u8,
+                            (127.0 + 127.0 * (phase + 4.2 - time * 0.3).sin()) as u8,
+                        )
+                    })
+                    .collect();
+                for (idx_row, row) in rows.enumerate() {
+                    for (idx_col, pxl) in row.iter_mut().enumerate() {
+                        let sum = cols[idx_col] + row_waves[idx_row] + diagonals[idx_col + idx_row];
+                        *pxl = palette[((sum + 3.0) / 6.0 * 255.0) as usize & 255];
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Clears to the pretty red, green and blue gradient, which is the default.
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_set_background_classic(renderer: *mut Renderer) {
+    (*renderer).set_background(Background::Classic);
+}
+
+/// Clears to a single colour, given as `0xRRGGBB`.
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_set_background_solid(renderer: *mut Renderer, color: u32) {
+    (*renderer).set_background(Background::Solid(Pixel::from_hex(color)));
+}
+
+/// Clears to a gradient across the screen, at `angle` radians from the x axis. Colours are `0xRRGGBB`.
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_set_background_linear_gradient(
+    renderer: *mut Renderer,
+    from: u32,
+    to: u32,
+    angle: f32,
+) {
+    (*renderer).set_background(Background::LinearGradient {
+        from: Pixel::from_hex(from),
+        to: Pixel::from_hex(to),
+        angle,
+    });
+}
+
+/// Clears to a gradient from the centre of the screen out. Colours are `0xRRGGBB`.
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_set_background_radial_gradient(
+    renderer: *mut Renderer,
+    inner: u32,
+    outer: u32,
+) {
+    (*renderer).set_background(Background::RadialGradient {
+        inner: Pixel::from_hex(inner),
+        outer: Pixel::from_hex(outer),
+    });
+}
+
+/// Clears to a checkerboard of `size` pixel squares. Colours are `0xRRGGBB`.
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_set_background_checkerboard(
+    renderer: *mut Renderer,
+    color1: u32,
+    color2: u32,
+    size: u32,
+) {
+    (*renderer).set_background(Background::Checkerboard {
+        color1: Pixel::from_hex(color1),
+        color2: Pixel::from_hex(color2),
+        size,
+    });
+}
+
+/// Clears to stars over a `0xRRGGBB` sky. The same seed always gives the same stars.
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_set_background_starfield(renderer: *mut Renderer, sky: u32, seed: u32) {
+    (*renderer).set_background(Background::Starfield {
+        sky: Pixel::from_hex(sky),
+        seed,
+    });
+}
+
+/// Clears to a `width` by `height` RGBA image, stretched to fill the screen. The pixels are copied. Does nothing
+/// if `pixels` is null.
+///
+/// # Safety
+/// `renderer` must come from [mn_create] and `pixels` must be null or point to `width * height` pixels.
+#[no_mangle]
+pub unsafe extern "C" fn mn_set_background_image(
+    renderer: *mut Renderer,
+    pixels: *const Pixel,
+    width: u32,
+    height: u32,
+) {
+    if pixels.is_null() {
+        return;
+    }
+    let mut image = FrameBuffer::new(width as usize, height as usize);
+    let len = image.pixels().len();
+    image
+        .pixels_mut()
+        .copy_from_slice(core::slice::from_raw_parts(pixels, len));
+    (*renderer).set_background(Background::Image(image));
+}
+
+/// Clears to an animated plasma.
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_set_background_plasma(renderer: *mut Renderer) {
+    (*renderer).set_background(Background::Plasma);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stars(seed: u32) -> FrameBuffer {
+        let mut frame_buffer = FrameBuffer::new(120, 90);
+        Background::Starfield {
+            sky: Pixel::from_hex(0x05050f),
+            seed,
+        }
+        .draw(&mut frame_buffer, 0.0);
+        frame_buffer
+    }
+
+    #[test]
+    fn starfield_seeds() {
+        assert_eq!(stars(0x5eed).pixels(), stars(0x5eed).pixels());
+        for seed in [0, 1, 2, 3, 0x5eed, u32::MAX] {
+            let sky = stars(seed);
+            let star_count = sky.pixels().iter().filter(|pixel| pixel.r > 0x05).count();
+            assert!(star_count > 20, "seed {seed} has {star_count} stars");
+            assert_ne!(sky.pixels(), stars(seed ^ 1).pixels(), "seed {seed}");
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//! Camera control for interactive viewers. [OrbitController] turns mouse drags, scrolls and time into a view of
+//! the mesh: turning it round like a turntable, moving it about the screen and zooming in and out. It knows
+//! nothing about where the input comes from, so any front-end can drive it with whatever input it has.
+//!
+//! Every [Renderer] has one for its demo mesh, see [Renderer::render_view], which the C and wasm front-ends drive
+//! through the `mn_orbit_*` exports.
+//!
+//! ```
+//! use meenle_noonle::camera::{Drag, OrbitController};
+//!
+//! let mut view = OrbitController::new();
+//! view.auto_spin = false;
+//! view.start_drag(Drag::Pan, 10.0, 10.0);
+//! view.drag_to(30.0, 5.0);
+//! view.end_drag();
+//! assert_eq!(view.pan, [20.0, -5.0]);
+//! view.zoom(2);
+//! assert!(view.zoom > 1.0);
+//! view.reset();
+//! assert_eq!(view.zoom, 1.0);
+//! ```
+
+use crate::*;
+use core::f32::consts::TAU;
+
+/// How far a drag turns the mesh, in radians per pixel.
+const ORBIT_SPEED: f32 = TAU / 500.0;
+/// How much each step of [OrbitController::zoom] zooms in.
+const ZOOM_STEP: f32 = 1.1;
+/// How far in and out [OrbitController::zoom] goes.
+const ZOOM_RANGE: (f32, f32) = (0.1, 20.0);
+
+/// What dragging does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Drag {
+    /// Turns the mesh about its centre.
+    Orbit,
+    /// Moves the mesh about the screen.
+    Pan,
+}
+
+impl Drag {
+    /// Picks what dragging does by number, for the C exports: 0 orbits and 1 pans.
+    pub fn from_id(id: u32) -> Option<Drag> {
+        Some(match id {
+            0 => Drag::Orbit,
+            1 => Drag::Pan,
+            _ => return None,
+        })
+    }
+}
+
+/// Orbit, pan and zoom camera. Positions and distances are in frame buffer pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitController {
+    /// Turn about the vertical axis, in radians.
+    pub yaw: f32,
+    /// Tilt toward or away from the viewer, in radians, kept within a quarter turn either way.
+    pub pitch: f32,
+    /// Scale of the mesh on the screen.
+    pub zoom: f32,
+    /// Offset of the mesh from the centre of the screen.
+    pub pan: [f32; 2],
+    /// Keeps the mesh turning when it isn't being dragged.
+    pub auto_spin: bool,
+    /// Seconds per turn when spinning by itself.
+    pub rotrate: f32,
+    /// Frame buffer pixels per unit of screen space. On high-DPI screens the frame buffer has more pixels than
+    /// the screen has units, and the mesh is scaled up by this so it stays the same size.
+    pub pixel_ratio: f32,
+    /// What the drag in progress does, and where it got to.
+    drag: Option<(Drag, f32, f32)>,
+    /// Time of the last [OrbitController::update].
+    last_time: Option<f32>,
+}
+
+impl Default for OrbitController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OrbitController {
+    /// A view straight on, spinning once every 5 seconds, like [Renderer::render_spin].
+    pub fn new() -> OrbitController {
+        OrbitController {
+            yaw: 0.0,
+            pitch: 0.0,
+            zoom: 1.0,
+            pan: [0.0, 0.0],
+            auto_spin: true,
+            rotrate: 5.0,
+            pixel_ratio: 1.0,
+            drag: None,
+            last_time: None,
+        }
+    }
+
+    /// Starts dragging from `(x, y)`, say when a mouse button goes down. Left drags usually orbit and right or
+    /// middle drags pan.
+    pub fn start_drag(&mut self, drag: Drag, x: f32, y: f32) {
+        self.drag = Some((drag, x, y));
+    }
+
+    /// Moves the drag in progress on to `(x, y)`. Does nothing if there isn't one.
+    pub fn drag_to(&mut self, x: f32, y: f32) {
+        let Some((drag, last_x, last_y)) = self.drag else {
+            return;
+        };
+        match drag {
+            Drag::Orbit => self.orbit(x - last_x, y - last_y),
+            Drag::Pan => self.move_by(x - last_x, y - last_y),
+        }
+        self.drag = Some((drag, x, y));
+    }
+
+    pub fn end_drag(&mut self) {
+        self.drag = None;
+    }
+
+    /// Whether there's a drag in progress.
+    pub fn dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Turns the mesh as if it were dragged `(dx, dy)` pixels: sideways spins it and up and down tilts it.
+    pub fn orbit(&mut self, dx: f32, dy: f32) {
+        self.yaw = (self.yaw + dx * ORBIT_SPEED) % TAU;
+        self.pitch = (self.pitch - dy * ORBIT_SPEED).clamp(-TAU / 4.0, TAU / 4.0);
+    }
+
+    /// Moves the mesh `(dx, dy)` pixels across the screen.
+    pub fn move_by(&mut self, dx: f32, dy: f32) {
+        self.pan = [self.pan[0] + dx, self.pan[1] + dy];
+    }
+
+    /// Zooms in by `steps` scroll steps, or out for negative steps.
+    pub fn zoom(&mut self, steps: i32) {
+        let step = if steps > 0 {
+            ZOOM_STEP
+        } else {
+            1.0 / ZOOM_STEP
+        };
+        for _ in 0..steps.unsigned_abs() {
+            self.zoom *= step;
+        }
+        self.zoom = self.zoom.clamp(ZOOM_RANGE.0, ZOOM_RANGE.1);
+    }
+
+    /// Goes back to the view straight on, leaving the spin settings and pixel ratio as they are.
+    pub fn reset(&mut self) {
+        *self = OrbitController {
+            auto_spin: self.auto_spin,
+            rotrate: self.rotrate,
+            pixel_ratio: self.pixel_ratio,
+            last_time: self.last_time,
+            ..OrbitController::new()
+        };
+    }
+
+    /// Moves the view on to `time_seconds`, spinning the mesh by however long it's been since the last update,
+    /// unless it's being dragged or [OrbitController::auto_spin] is off.
+    pub fn update(&mut self, time_seconds: f32) {
+        let elapsed = self
+            .last_time
+            .map_or(0.0, |last_time| (time_seconds - last_time).max(0.0));
+        self.last_time = Some(time_seconds);
+        if self.auto_spin && !self.dragging() {
+            self.yaw = (self.yaw + elapsed * TAU / self.rotrate) % TAU;
+        }
+    }
+
+    /// Puts the mesh where the view has it: turned, scaled and moved.
+    pub fn apply(&self, mesh: &mut Mesh) {
+        mesh.rot(Axis::Y, self.yaw);
+        mesh.rot(Axis::X, self.pitch);
+        mesh.scale(self.zoom * self.pixel_ratio);
+        mesh.translate(Vec3::from([self.pan[0], self.pan[1], 0.0]));
+    }
+}
+
+impl<F: PixelFormat> Renderer<F> {
+    /// The camera [Renderer::render_view] draws the demo mesh with.
+    pub fn view(&self) -> &OrbitController {
+        &self.view
+    }
+
+    pub fn view_mut(&mut self) -> &mut OrbitController {
+        &mut self.view
+    }
+}
+
+/// See [Renderer::render_view].
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_render_view(renderer: *mut Renderer, time_seconds: f32) {
+    (*renderer).render_view(time_seconds);
+}
+
+/// Starts dragging the view from pixel `(x, y)`, orbiting or panning as picked by [Drag::from_id]. Other ids are
+/// ignored. See [OrbitController::start_drag].
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_orbit_start_drag(renderer: *mut Renderer, drag: u32, x: f32, y: f32) {
+    if let Some(drag) = Drag::from_id(drag) {
+        (*renderer).view.start_drag(drag, x, y);
+    }
+}
+
+/// See [OrbitController::drag_to].
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_orbit_drag_to(renderer: *mut Renderer, x: f32, y: f32) {
+    (*renderer).view.drag_to(x, y);
+}
+
+/// See [OrbitController::end_drag].
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_orbit_end_drag(renderer: *mut Renderer) {
+    (*renderer).view.end_drag();
+}
+
+/// See [OrbitController::zoom].
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_orbit_zoom(renderer: *mut Renderer, steps: i32) {
+    (*renderer).view.zoom(steps);
+}
+
+/// See [OrbitController::reset].
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_orbit_reset(renderer: *mut Renderer) {
+    (*renderer).view.reset();
+}
+
+/// Turns the view's spinning by itself on or off, see [OrbitController::auto_spin].
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_orbit_set_auto_spin(renderer: *mut Renderer, auto_spin: bool) {
+    (*renderer).view.auto_spin = auto_spin;
+}
@@ -0,0 +1,186 @@
+//! A small deflate compressor, so images can be saved without any dependencies. Repeats are found with a hash
+//! chain over a 32 KiB window, then everything is written with the fixed Huffman codes from RFC 1951, which avoids
+//! building code tables. It doesn't compress as well as zlib, but frame buffers are mostly runs of background and
+//! long repeats, so it does well enough.
+
+#[cfg(target_arch = "powerpc")]
+use crate::*;
+
+const WINDOW: usize = 1 << 15;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// How many earlier positions with the same hash are tried before settling for the best match so far.
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: u32 = 14;
+
+/// Base lengths for length codes 257 to 285, then how many extra bits each takes.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+/// Base distances for distance codes 0 to 29, then how many extra bits each takes.
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Writes bits least significant first, the way deflate packs them.
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u32,
+    bit_count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, count: u32) {
+        self.bits |= value << self.bit_count;
+        self.bit_count += count;
+        while self.bit_count >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+
+    /// Huffman codes are packed most significant bit first, unlike everything else.
+    fn write_code(&mut self, code: u32, len: u32) {
+        self.write(code.reverse_bits() >> (32 - len), len);
+    }
+
+    /// Writes a literal byte or length symbol with the fixed literal/length code.
+    fn write_symbol(&mut self, symbol: u16) {
+        let symbol = symbol as u32;
+        match symbol {
+            0..=143 => self.write_code(0b00110000 + symbol, 8),
+            144..=255 => self.write_code(0b110010000 + symbol - 144, 9),
+            256..=279 => self.write_code(symbol - 256, 7),
+            _ => self.write_code(0b11000000 + symbol - 280, 8),
+        }
+    }
+
+    fn write_match(&mut self, len: usize, dist: usize) {
+        let idx_len = LENGTH_BASE.partition_point(|&base| base as usize <= len) - 1;
+        self.write_symbol(257 + idx_len as u16);
+        self.write(
+            (len - LENGTH_BASE[idx_len] as usize) as u32,
+            LENGTH_EXTRA[idx_len] as u32,
+        );
+        let idx_dist = DIST_BASE.partition_point(|&base| base as usize <= dist) - 1;
+        self.write_code(idx_dist as u32, 5);
+        self.write(
+            (dist - DIST_BASE[idx_dist] as usize) as u32,
+            DIST_EXTRA[idx_dist] as u32,
+        );
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bit_count > 0 {
+            self.bytes.push(self.bits as u8);
+        }
+        self.bytes
+    }
+}
+
+fn hash(data: &[u8]) -> usize {
+    let key = (data[0] as u32) << 16 | (data[1] as u32) << 8 | data[2] as u32;
+    (key.wrapping_mul(0x9e3779b1) >> (32 - HASH_BITS)) as usize
+}
+
+/// Compresses the data into a raw deflate stream, as one fixed Huffman block.
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut out = BitWriter {
+        bytes: Vec::with_capacity(data.len() / 4),
+        bits: 0,
+        bit_count: 0,
+    };
+    // final block, fixed Huffman codes
+    out.write(1, 1);
+    out.write(0b01, 2);
+
+    // head[hash] is the latest position + 1 with that hash, and prev[pos % WINDOW] the one before it
+    let mut head = vec![0usize; 1 << HASH_BITS];
+    let mut prev = vec![0usize; WINDOW];
+    let insert = |head: &mut [usize], prev: &mut [usize], pos: usize| {
+        if pos + MIN_MATCH <= data.len() {
+            let idx_hash = hash(&data[pos..]);
+            prev[pos % WINDOW] = head[idx_hash];
+            head[idx_hash] = pos + 1;
+        }
+    };
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let (mut best_len, mut best_dist) = (0, 0);
+        if pos + MIN_MATCH <= data.len() {
+            let max_len = MAX_MATCH.min(data.len() - pos);
+            let mut candidate = head[hash(&data[pos..])];
+            for _ in 0..MAX_CHAIN {
+                if candidate == 0 || pos - (candidate - 1) > WINDOW - 1 {
+                    break;
+                }
+                let start = candidate - 1;
+                let len = data[start..start + max_len]
+                    .iter()
+                    .zip(&data[pos..pos + max_len])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    (best_len, best_dist) = (len, pos - start);
+                    if len == max_len {
+                        break;
+                    }
+                }
+                let next = prev[start % WINDOW];
+                // older entries get overwritten as the window slides, so stop if the chain goes forwards
+                if next >= candidate {
+                    break;
+                }
+                candidate = next;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            out.write_match(best_len, best_dist);
+            for idx in pos..pos + best_len {
+                insert(&mut head, &mut prev, idx);
+            }
+            pos += best_len;
+        } else {
+            out.write_symbol(data[pos] as u16);
+            insert(&mut head, &mut prev, pos);
+            pos += 1;
+        }
+    }
+    out.write_symbol(256);
+    out.finish()
+}
+
+/// Compresses the data into a zlib stream: a deflate stream with a header and an Adler-32 checksum, as PNG wants.
+pub fn zlib(data: &[u8]) -> Vec<u8> {
+    // deflate, 32 KiB window, no preset dictionary, and a check value making the header a multiple of 31
+    let mut out = vec![0x78, 0x01];
+    out.extend(deflate(data));
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 bytes is the most that can be summed before b could overflow
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    b << 16 | a
+}
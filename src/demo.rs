@@ -1,83 +1,259 @@
-//! Demonstration of the meenle-noonle library. To use, first call [generate_background], then pick your mesh with
-//! [set_mesh], and use [get_buffer] to get the frame buffer where the output will be rendered. Call [render_spin]
-//! every frame to update the frame buffer. NOTE: The demo is not thread safe. You must wait for [set_mesh] to finish
-//! execution before rendering.
+//! Demonstration of the meenle-noonle library. To use, first call [Renderer::generate_background], then pick your
+//! mesh with [Renderer::set_mesh], and use [Renderer::buffer] to get the frame buffer where the output will be
+//! rendered. Call [Renderer::render_spin] every frame to update the frame buffer. Each [Renderer] has its own demo
+//! mesh, so different renderers can show different meshes on different threads.
 //!
 //! Example usage:
 //! ```
-//! meenle_noonle::generate_background();
-//! meenle_noonle::demo::set_mesh(0);
-//! pFFB = meenle_noonle::get_buffer();
+//! let mut renderer = meenle_noonle::Renderer::new();
+//! renderer.generate_background();
+//! renderer.set_mesh(0);
 //!
-//! loop {
-//!     meenle_noonle::demo::render_spin(epoch, 5.0);
-//!     sleep(1.0 / 60.0);
+//! for frame in 0..60 {
+//!     renderer.render_spin(frame as f32 / 60.0, 5.0);
+//!     let frame_buffer = renderer.buffer();
+//!     // ...show frame_buffer.pixels() and wait for the next frame
 //! }
 //! ```
 
+use crate::background::Background;
+use crate::gif::GifEncoder;
 use crate::meshes;
+use crate::png::ApngEncoder;
+use crate::skinning::Skeleton;
 use crate::*;
 use core::f32::consts::TAU;
 
-static mut DEMO_MESH: Option<Mesh> = None;
+/// Number of meshes [Renderer::set_mesh] can pick from.
+pub const MESH_COUNT: u32 = 4;
+/// Names of the meshes [Renderer::set_mesh] picks, by id.
+pub const MESH_NAMES: [&str; MESH_COUNT as usize] = ["monkey", "icosphere", "cube", "tentacle"];
+/// Number of backgrounds [Renderer::set_demo_background] can pick from.
+pub const BACKGROUND_COUNT: u32 = 7;
 
-#[no_mangle]
-pub extern "C" fn set_mesh(id: u32) {
-    unsafe {
+/// One of the still demo meshes, sized and turned the right way up for the screen: 0 is the monkey, 1 the
+/// icosphere and 2 the cube. [None] for any other id.
+pub fn mesh(id: u32) -> Option<Mesh> {
+    match id {
+        0 => {
+            let mut demo_mesh = meshes::monkey();
+            demo_mesh.scale(100.0);
+            demo_mesh.rot(Axis::X, TAU / 2.0);
+            Some(demo_mesh)
+        }
+        1 => {
+            let mut demo_mesh = meshes::icosphere();
+            demo_mesh.scale(50.0);
+            demo_mesh.rot(Axis::X, TAU / 2.0);
+            Some(demo_mesh)
+        }
+        2 => Some(Mesh::cube(
+            Vec3::from([-50.0, -50.0, -50.0]),
+            Vec3::from([50.0, 50.0, 50.0]),
+        )),
+        _ => None,
+    }
+}
+
+impl<F: PixelFormat> Renderer<F> {
+    /// Picks the demo mesh: 0 is the monkey, 1 the icosphere, 2 the cube and 3 the animated tentacle. Other ids are
+    /// ignored.
+    pub fn set_mesh(&mut self, id: u32) {
+        if id < MESH_COUNT {
+            self.demo_skin = None;
+        }
         match id {
-            0 => {
-                DEMO_MESH = Some(meshes::monkey());
-                if let Some(ref mut demo_mesh) = DEMO_MESH {
-                    demo_mesh.scale(100.0);
-                    demo_mesh.rot(Axis::X, TAU / 2.0);
+            3 => self.demo_skin = Some(meshes::tentacle()),
+            _ => {
+                if let Some(demo_mesh) = mesh(id) {
+                    self.demo_mesh = Some(demo_mesh);
                 }
             }
-            1 => {
-                DEMO_MESH = Some(meshes::icosphere());
-                if let Some(ref mut demo_mesh) = DEMO_MESH {
-                    demo_mesh.scale(50.0);
-                    demo_mesh.rot(Axis::X, TAU / 2.0);
-                }
-            }
-            2 => {
-                DEMO_MESH = Some(
-                    Mesh::cube(
-                        Vec3::from([-50.0, -50.0, -50.0]),
-                        Vec3::from([50.0, 50.0, 50.0]),
-                    )
-                    .into(),
-                )
-                .into()
-            }
-            _ => (),
         }
     }
-}
 
-#[no_mangle]
-pub extern "C" fn scale_mesh_to_screen(_proportion: f32) {
-    unsafe {
-        if let Some(ref mut demo_mesh) = DEMO_MESH {
-            let oba = demo_mesh
+    /// Shows a mesh of your own, like one from a [model] file, in place of the built-in ones.
+    pub fn set_demo_mesh(&mut self, mesh: Mesh) {
+        self.demo_skin = None;
+        self.demo_mesh = Some(mesh);
+    }
+
+    /// The demo mesh as last rendered, or as picked if it hasn't been rendered yet.
+    pub fn demo_mesh(&self) -> Option<&Mesh> {
+        self.demo_mesh.as_ref()
+    }
+
+    /// Picks one of the demo backgrounds: 0 is the classic gradient, then a solid colour, a linear gradient, a
+    /// radial gradient, a checkerboard, a starfield and the plasma. Other ids are ignored.
+    pub fn set_demo_background(&mut self, id: u32) {
+        let background = match id {
+            0 => Background::Classic,
+            1 => Background::Solid(Pixel::from_hex(0x0b0b44)),
+            2 => Background::LinearGradient {
+                from: Pixel::from_hex(0xff5e62),
+                to: Pixel::from_hex(0x2b5876),
+                angle: TAU / 8.0,
+            },
+            3 => Background::RadialGradient {
+                inner: Pixel::from_hex(0x3a6073),
+                outer: Pixel::from_hex(0x0b0b1e),
+            },
+            4 => Background::Checkerboard {
+                color1: Pixel::from_hex(0x303030),
+                color2: Pixel::from_hex(0x505050),
+                size: 25,
+            },
+            5 => Background::Starfield {
+                sky: Pixel::from_hex(0x05050f),
+                seed: 0x5eed,
+            },
+            6 => Background::Plasma,
+            _ => return,
+        };
+        self.set_background(background);
+    }
+
+    /// Scales the demo mesh about its origin so that, however it's turned, it reaches `proportion` of the way
+    /// across the shorter side of the frame. Proportions of 0 or less are ignored.
+    pub fn scale_mesh_to_screen(&mut self, proportion: f32) {
+        let screen = self.buffer.width.min(self.buffer.height) as f32;
+        if let Some(ref mut demo_mesh) = self.demo_mesh {
+            let furthest = demo_mesh
                 .tris
                 .iter()
                 .flat_map(|tri| tri.verts)
-                .flat_map(|vert| [vert.x, vert.y, vert.z])
-                .reduce(f32::max);
-            dbg!(oba);
+                .flat_map(|vert| [vert.x.abs(), vert.y.abs(), vert.z.abs()])
+                .fold(0.0, f32::max);
+            if proportion > 0.0 && furthest > f32::EPSILON {
+                demo_mesh.scale(proportion * screen / (2.0 * furthest));
+            }
         }
     }
-}
 
-/// Render the spinning mesh animation into the frame buffer.
-#[no_mangle]
-pub extern "C" fn render_spin(time_seconds: f32, rotrate: f32) {
-    unsafe {
-        if let Some(ref mut demo_mesh) = DEMO_MESH {
+    /// Render the spinning mesh animation into the frame buffer.
+    pub fn render_spin(&mut self, time_seconds: f32, rotrate: f32) {
+        self.animate_background(time_seconds);
+        self.pose_demo_skin(time_seconds);
+        if let Some(ref demo_mesh) = self.demo_mesh {
             let mut opa = demo_mesh.clone();
             opa.rot(Axis::Y, (time_seconds * TAU / rotrate) % TAU);
-            fill_buffer();
-            opa.render();
+            self.render_mesh(&opa);
+            self.spun_mesh = Some(opa);
+        }
+    }
+
+    /// Renders the demo mesh as [Renderer::view] sees it into the frame buffer. Like [Renderer::render_spin], but
+    /// the camera can be dragged about and zoomed, for interactive viewers.
+    pub fn render_view(&mut self, time_seconds: f32) {
+        self.animate_background(time_seconds);
+        self.pose_demo_skin(time_seconds);
+        self.view.update(time_seconds);
+        if let Some(ref demo_mesh) = self.demo_mesh {
+            let mut viewed = demo_mesh.clone();
+            self.view.apply(&mut viewed);
+            self.render_mesh(&viewed);
+            self.spun_mesh = Some(viewed);
+        }
+    }
+
+    /// Poses the animated demo mesh, if it's the one picked, into the demo mesh.
+    fn pose_demo_skin(&mut self, time_seconds: f32) {
+        if let Some((ref skinned_mesh, ref mut skeleton)) = self.demo_skin {
+            wiggle(skeleton, time_seconds);
+            self.demo_mesh = Some(skinned_mesh.pose(skeleton));
         }
     }
+
+    /// Renders `frames` frames of [Renderer::render_spin], `fps` to the second starting from 0 seconds, and hands
+    /// each one to `frame` as it's done.
+    pub fn record_spin(
+        &mut self,
+        frames: usize,
+        fps: f32,
+        rotrate: f32,
+        mut frame: impl FnMut(&FrameBuffer<F>),
+    ) {
+        for idx_frame in 0..frames {
+            self.render_spin(idx_frame as f32 / fps, rotrate);
+            frame(&self.buffer);
+        }
+    }
+}
+
+impl Renderer {
+    /// Records the spinning mesh as a looping GIF. See [Renderer::record_spin].
+    pub fn spin_gif(&mut self, frames: usize, fps: f32, rotrate: f32) -> Vec<u8> {
+        let mut gif = GifEncoder::new(self.buffer.width, self.buffer.height, fps);
+        self.record_spin(frames, fps, rotrate, |frame_buffer| {
+            gif.add_frame(frame_buffer)
+        });
+        gif.finish()
+    }
+
+    /// Records the spinning mesh as a looping animated PNG. See [Renderer::record_spin].
+    pub fn spin_apng(&mut self, frames: usize, fps: f32, rotrate: f32) -> Vec<u8> {
+        let mut apng = ApngEncoder::new(self.buffer.width, self.buffer.height, fps);
+        self.record_spin(frames, fps, rotrate, |frame_buffer| {
+            apng.add_frame(frame_buffer)
+        });
+        apng.finish()
+    }
+}
+
+/// Sways every joint of the skeleton back and forth, each a little behind its parent.
+fn wiggle(skeleton: &mut Skeleton, time_seconds: f32) {
+    for idx_joint in 0..skeleton.joints.len() {
+        let phase = time_seconds * 2.0 - idx_joint as f32 * 0.8;
+        skeleton.set_pose(
+            idx_joint,
+            Vec3::from([0.2 * (phase * 0.7).sin(), 0.0, 0.35 * phase.sin()]),
+        );
+    }
+}
+
+/// See [Renderer::set_mesh].
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_set_mesh(renderer: *mut Renderer, id: u32) {
+    (*renderer).set_mesh(id);
+}
+
+/// Number of triangles in the demo mesh, 0 if there isn't one. See [Renderer::demo_mesh].
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_triangle_count(renderer: *const Renderer) -> u32 {
+    (*renderer)
+        .demo_mesh()
+        .map_or(0, |demo_mesh| demo_mesh.tris.len() as u32)
+}
+
+/// See [Renderer::set_demo_background].
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_set_demo_background(renderer: *mut Renderer, id: u32) {
+    (*renderer).set_demo_background(id);
+}
+
+/// See [Renderer::scale_mesh_to_screen].
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_scale_mesh_to_screen(renderer: *mut Renderer, proportion: f32) {
+    (*renderer).scale_mesh_to_screen(proportion);
+}
+
+/// Render the spinning mesh animation into the frame buffer.
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_render_spin(renderer: *mut Renderer, time_seconds: f32, rotrate: f32) {
+    (*renderer).render_spin(time_seconds, rotrate);
 }
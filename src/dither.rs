@@ -0,0 +1,181 @@
+//! Dithering, so gradients don't band when they're cut down to a few colours for RGB565, greyscale or 1-bit
+//! screens. [dither()] works over a finished RGBA frame buffer, leaving only colours from the palette, ready to be
+//! converted with [FrameBuffer::convert]. Renderers drawing straight into a low bit depth format can dither their
+//! background instead, with [Renderer::set_dither].
+
+use crate::*;
+
+/// How colours between the palette's are spread over neighbouring pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dither {
+    /// An 8x8 Bayer matrix of thresholds. Gives a regular crosshatch, and every pixel only depends on itself, so
+    /// it doesn't crawl when only part of the picture changes.
+    Bayer,
+    /// Floyd–Steinberg error diffusion: all of each pixel's error is passed on to the four pixels right of and
+    /// below it.
+    FloydSteinberg,
+    /// Atkinson error diffusion: three quarters of the error is passed on, over six pixels. Higher contrast than
+    /// Floyd–Steinberg, as on the original Macintosh.
+    Atkinson,
+}
+
+/// The colours dithering leaves.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Palette {
+    /// Evenly spaced levels of red, green and blue, each at least 2.
+    Levels([u16; 3]),
+    /// Evenly spaced shades of grey, from black to white, at least 2. Colours are turned grey first.
+    Gray(u16),
+    /// Any colours. Each pixel gets the nearest one.
+    Colors(Vec<Pixel>),
+}
+
+impl Palette {
+    /// The colours [format::Rgb565] stores.
+    pub const RGB565: Palette = Palette::Levels([32, 64, 32]);
+    /// Black and white, for [format::Mono1].
+    pub const MONO: Palette = Palette::Gray(2);
+
+    /// Roughly how far apart neighbouring colours are on each channel, which is how far Bayer dithering nudges
+    /// colours.
+    fn spacing(&self) -> [i32; 3] {
+        match self {
+            Palette::Levels(levels) => levels.map(|levels| 255 / (levels.max(2) as i32 - 1)),
+            Palette::Gray(levels) => [255 / (levels.max(&2) - 1) as i32; 3],
+            Palette::Colors(colors) => {
+                // as if they were a cube of levels, the largest that fits. `f32::cbrt` needs std
+                let levels = (2..)
+                    .take_while(|levels| levels * levels * levels <= colors.len())
+                    .last();
+                [255 / (levels.unwrap_or(2) as i32 - 1); 3]
+            }
+        }
+    }
+
+    /// The palette colour closest to the colour, whose channels can be past 0 to 255 with the error added in.
+    fn nearest(&self, color: [i32; 3]) -> [i32; 3] {
+        let level = |value: i32, levels: u16| {
+            let steps = levels.max(2) as i32 - 1;
+            let step = (value.clamp(0, 255) * steps + 127) / 255;
+            (step * 255 + steps / 2) / steps
+        };
+        match self {
+            Palette::Levels(levels) => [
+                level(color[0], levels[0]),
+                level(color[1], levels[1]),
+                level(color[2], levels[2]),
+            ],
+            Palette::Gray(levels) => [level(color[0], *levels); 3],
+            Palette::Colors(colors) => {
+                let distance = |pxl: &&Pixel| {
+                    let diff = |a: u8, b: i32| (a as i32 - b).pow(2);
+                    diff(pxl.r, color[0]) + diff(pxl.g, color[1]) + diff(pxl.b, color[2])
+                };
+                colors
+                    .iter()
+                    .min_by_key(distance)
+                    .map_or(color, |pxl| [pxl.r as i32, pxl.g as i32, pxl.b as i32])
+            }
+        }
+    }
+}
+
+/// Thresholds for Bayer dithering, 0 to 63, in an order that spreads each one as far from the last as it can.
+const BAYER: [[u8; 8]; 8] = {
+    let mut matrix = [[0; 8]; 8];
+    let mut y = 0;
+    while y < 8 {
+        let mut x = 0;
+        while x < 8 {
+            // interleave the bits of x ^ y and y, most significant first
+            let (xor, mut value, mut bit) = (x ^ y, 0, 0);
+            while bit < 3 {
+                value |= ((xor >> bit) & 1) << (5 - 2 * bit) | ((y >> bit) & 1) << (4 - 2 * bit);
+                bit += 1;
+            }
+            matrix[y][x] = value as u8;
+            x += 1;
+        }
+        y += 1;
+    }
+    matrix
+};
+
+/// Dithers the frame buffer in place, leaving only colours from the palette. Alpha is left alone.
+pub fn dither(frame_buffer: &mut FrameBuffer, palette: &Palette, dither: Dither) {
+    let width = frame_buffer.width();
+    dither_pixels(frame_buffer.pixels_mut(), width, palette, dither);
+}
+
+/// Dithers rows of `width` pixels.
+pub(crate) fn dither_pixels(pixels: &mut [Pixel], width: usize, palette: &Palette, dither: Dither) {
+    let channels = |pxl: &Pixel| match palette {
+        Palette::Gray(_) => {
+            let luma = (pxl.r as i32 * 77 + pxl.g as i32 * 150 + pxl.b as i32 * 29) >> 8;
+            [luma; 3]
+        }
+        _ => [pxl.r as i32, pxl.g as i32, pxl.b as i32],
+    };
+    let store = |pxl: &mut Pixel, [r, g, b]: [i32; 3]| {
+        *pxl = Pixel {
+            r: r as u8,
+            g: g as u8,
+            b: b as u8,
+            a: pxl.a,
+        };
+    };
+
+    // error diffusion weights, in eighths or sixteenths, as (dx, dy, weight)
+    let (spread, denominator): (&[(isize, usize, i32)], i32) = match dither {
+        Dither::Bayer => {
+            let spacing = palette.spacing();
+            for (idx_row, row) in pixels.chunks_exact_mut(width.max(1)).enumerate() {
+                for (idx_col, pxl) in row.iter_mut().enumerate() {
+                    // from half a step down to half a step up
+                    let threshold = BAYER[idx_row % 8][idx_col % 8] as i32 * 2 - 63;
+                    let mut color = channels(pxl);
+                    for (value, spacing) in color.iter_mut().zip(spacing) {
+                        *value += threshold * spacing / 128;
+                    }
+                    store(pxl, palette.nearest(color));
+                }
+            }
+            return;
+        }
+        Dither::FloydSteinberg => (&[(1, 0, 7), (-1, 1, 3), (0, 1, 5), (1, 1, 1)], 16),
+        Dither::Atkinson => (
+            &[
+                (1, 0, 1),
+                (2, 0, 1),
+                (-1, 1, 1),
+                (0, 1, 1),
+                (1, 1, 1),
+                (0, 2, 1),
+            ],
+            8,
+        ),
+    };
+
+    // error carried into this row and the two below it, with two columns of margin either side
+    let padded = width + 4;
+    let mut errors = vec![[0i32; 3]; padded * 3];
+    for row in pixels.chunks_exact_mut(width.max(1)) {
+        for (idx_col, pxl) in row.iter_mut().enumerate() {
+            let mut color = channels(pxl);
+            for (value, error) in color.iter_mut().zip(errors[idx_col + 2]) {
+                *value += error / denominator;
+            }
+            let nearest = palette.nearest(color);
+            for &(dx, dy, weight) in spread {
+                let idx = dy * padded + (idx_col as isize + 2 + dx) as usize;
+                for channel in 0..3 {
+                    errors[idx][channel] += (color[channel] - nearest[channel]) * weight;
+                }
+            }
+            store(pxl, nearest);
+        }
+        // move on a row
+        errors.copy_within(padded.., 0);
+        errors[padded * 2..].fill([0; 3]);
+    }
+}
@@ -0,0 +1,389 @@
+//! Full-screen effects run over the frame buffer after the mesh is drawn: glow, CRT scanlines, chromatic
+//! aberration, vignetting, blur and motion trails. Add them to the renderer's [EffectChain] with
+//! [Renderer::effects_mut]. Effects that only look at one pixel at a time work in place; the rest ping-pong
+//! between the frame and a scratch buffer.
+//!
+//! Effects change the whole frame, so while any are on, [Renderer::dirty_rect] is the whole frame buffer.
+
+use crate::*;
+
+/// Number of effects [Effect::demo] can pick from.
+pub const EFFECT_COUNT: u32 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    /// Makes bright pixels glow: pixels brighter than `threshold`, 0 to 255, are blurred over `radius` pixels
+    /// and added back on, `strength` times over.
+    Bloom {
+        threshold: u8,
+        radius: u32,
+        strength: f32,
+    },
+    /// Darkens every other row, like an old CRT.
+    Scanlines { darkness: f32 },
+    /// Splits red and blue apart towards the left and right edges, by up to `offset` pixels.
+    ChromaticAberration { offset: u32 },
+    /// Darkens the corners.
+    Vignette { strength: f32 },
+    /// Box blur over `radius` pixels either way.
+    Blur { radius: u32 },
+    /// Leaves fading trails behind moving lines, by blending towards the frame before where it was brighter.
+    /// Each frame keeps `persistence` of the last one's trails.
+    Trails { persistence: f32 },
+}
+
+impl Effect {
+    /// Picks one of the demo effects, with settings that suit the demo: 0 is bloom, then scanlines, chromatic
+    /// aberration, a vignette, a blur and motion trails.
+    pub fn demo(id: u32) -> Option<Effect> {
+        Some(match id {
+            0 => Effect::Bloom {
+                threshold: 240,
+                radius: 2,
+                strength: 0.8,
+            },
+            1 => Effect::Scanlines { darkness: 0.4 },
+            2 => Effect::ChromaticAberration { offset: 4 },
+            3 => Effect::Vignette { strength: 0.7 },
+            4 => Effect::Blur { radius: 1 },
+            5 => Effect::Trails { persistence: 0.8 },
+            _ => return None,
+        })
+    }
+
+    /// Whether both are the same kind of effect, whatever their settings.
+    fn same_kind(&self, other: &Effect) -> bool {
+        core::mem::discriminant(self) == core::mem::discriminant(other)
+    }
+}
+
+/// Effects run one after the other, in order, along with the buffers they need.
+#[derive(Debug, Clone, Default)]
+pub struct EffectChain {
+    pub effects: Vec<Effect>,
+    /// The frame being worked on, for frame buffers not stored as [Pixel]s.
+    frame: Vec<Pixel>,
+    scratch: Vec<Pixel>,
+    /// Second scratch buffer, for blurring bloom.
+    scratch2: Vec<Pixel>,
+    /// The last frame's output, for trails.
+    previous: Vec<Pixel>,
+}
+
+impl EffectChain {
+    pub fn new() -> EffectChain {
+        EffectChain::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    pub fn push(&mut self, effect: Effect) {
+        self.effects.push(effect);
+    }
+
+    /// Removes the effect if one of its kind is in the chain, or adds it to the end if not. Returns whether it's
+    /// on now.
+    pub fn toggle(&mut self, effect: Effect) -> bool {
+        let len = self.effects.len();
+        self.effects.retain(|other| !other.same_kind(&effect));
+        let on = self.effects.len() == len;
+        if on {
+            self.effects.push(effect);
+        } else if !self
+            .effects
+            .iter()
+            .any(|other| matches!(other, Effect::Trails { .. }))
+        {
+            self.previous = Vec::new();
+        }
+        on
+    }
+
+    /// Runs every effect over the frame buffer, which counts as drawn all over afterwards.
+    pub fn apply<F: PixelFormat>(&mut self, frame_buffer: &mut FrameBuffer<F>) {
+        if self.effects.is_empty() {
+            return;
+        }
+        let (width, height) = (frame_buffer.width(), frame_buffer.height());
+        let stride = frame_buffer.stride();
+        let mut frame = core::mem::take(&mut self.frame);
+        frame.resize(width * height, Pixel::default());
+        for (idx_row, row) in frame.chunks_exact_mut(width.max(1)).enumerate() {
+            let bytes = &frame_buffer.bytes()[idx_row * stride..(idx_row + 1) * stride];
+            for (x, pxl) in row.iter_mut().enumerate() {
+                *pxl = F::decode(bytes, x);
+            }
+        }
+        self.apply_pixels(&mut frame, width);
+        let bytes = frame_buffer.bytes_mut();
+        for (row, bytes) in frame
+            .chunks_exact(width.max(1))
+            .zip(bytes.chunks_exact_mut(stride.max(1)))
+        {
+            F::encode_row(row, bytes);
+        }
+        self.frame = frame;
+    }
+
+    /// Runs every effect over rows of `width` pixels, in place.
+    pub fn apply_pixels(&mut self, pixels: &mut [Pixel], width: usize) {
+        let width = width.max(1);
+        let height = pixels.len() / width;
+        for buffer in [&mut self.scratch, &mut self.scratch2] {
+            buffer.resize(pixels.len(), Pixel::default());
+        }
+        for effect in &self.effects {
+            match *effect {
+                Effect::Bloom {
+                    threshold,
+                    radius,
+                    strength,
+                } => {
+                    for (glow, pxl) in self.scratch.iter_mut().zip(pixels.iter()) {
+                        let luma =
+                            (pxl.r as u32 * 77 + pxl.g as u32 * 150 + pxl.b as u32 * 29) >> 8;
+                        let bright = luma > threshold as u32;
+                        *glow = if bright { *pxl } else { Pixel::default() };
+                    }
+                    blur_rows(&self.scratch, &mut self.scratch2, width, radius as usize);
+                    blur_columns(
+                        &self.scratch2,
+                        &mut self.scratch,
+                        width,
+                        height,
+                        radius as usize,
+                    );
+                    let scale = (strength * 256.0) as u32;
+                    let add = |value: u8, glow: u8| {
+                        (value as u32 + ((glow as u32 * scale) >> 8)).min(255) as u8
+                    };
+                    for (pxl, glow) in pixels.iter_mut().zip(&self.scratch) {
+                        pxl.r = add(pxl.r, glow.r);
+                        pxl.g = add(pxl.g, glow.g);
+                        pxl.b = add(pxl.b, glow.b);
+                    }
+                }
+                Effect::Scanlines { darkness } => {
+                    let dark = Pixel::rgb(0, 0, 0);
+                    for row in pixels.chunks_exact_mut(width).skip(1).step_by(2) {
+                        for pxl in row {
+                            *pxl = pxl.lerp(Pixel { a: pxl.a, ..dark }, darkness);
+                        }
+                    }
+                }
+                Effect::ChromaticAberration { offset } => {
+                    self.scratch.copy_from_slice(pixels);
+                    let centre = width as f32 / 2.0;
+                    for (pxls, src) in pixels
+                        .chunks_exact_mut(width)
+                        .zip(self.scratch.chunks_exact(width))
+                    {
+                        for (idx_col, pxl) in pxls.iter_mut().enumerate() {
+                            // more split the further from the centre
+                            let shift =
+                                ((idx_col as f32 - centre) / centre * offset as f32) as isize;
+                            let at = |dx: isize| {
+                                &src[(idx_col as isize + dx).clamp(0, width as isize - 1) as usize]
+                            };
+                            pxl.r = at(-shift).r;
+                            pxl.b = at(shift).b;
+                        }
+                    }
+                }
+                Effect::Vignette { strength } => {
+                    let (centre_x, centre_y) = (width as f32 / 2.0, height as f32 / 2.0);
+                    let corner = (centre_x * centre_x + centre_y * centre_y).max(1.0);
+                    for (idx_row, row) in pixels.chunks_exact_mut(width).enumerate() {
+                        let dy = idx_row as f32 - centre_y;
+                        for (idx_col, pxl) in row.iter_mut().enumerate() {
+                            let dx = idx_col as f32 - centre_x;
+                            let light = (1.0 - strength * (dx * dx + dy * dy) / corner).max(0.0);
+                            let dim = |value: u8| (value as f32 * light) as u8;
+                            *pxl = Pixel {
+                                r: dim(pxl.r),
+                                g: dim(pxl.g),
+                                b: dim(pxl.b),
+                                a: pxl.a,
+                            };
+                        }
+                    }
+                }
+                Effect::Blur { radius } => {
+                    blur_rows(pixels, &mut self.scratch, width, radius as usize);
+                    blur_columns(&self.scratch, pixels, width, height, radius as usize);
+                }
+                Effect::Trails { persistence } => {
+                    if self.previous.len() != pixels.len() {
+                        self.previous = pixels.to_vec();
+                    }
+                    for (pxl, previous) in pixels.iter_mut().zip(&mut self.previous) {
+                        let faded = pxl.lerp(*previous, persistence);
+                        pxl.r = pxl.r.max(faded.r);
+                        pxl.g = pxl.g.max(faded.g);
+                        pxl.b = pxl.b.max(faded.b);
+                        *previous = *pxl;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Box blurs each row of `src` into `dst`, over `radius` pixels either way, with a running sum. Pixels past the
+/// edges repeat the edge.
+fn blur_rows(src: &[Pixel], dst: &mut [Pixel], width: usize, radius: usize) {
+    for (src, dst) in src.chunks_exact(width).zip(dst.chunks_exact_mut(width)) {
+        blur_line(|idx| src[idx], |idx, pxl| dst[idx] = pxl, width, radius);
+    }
+}
+
+/// Box blurs each column of `src` into `dst`, like [blur_rows].
+fn blur_columns(src: &[Pixel], dst: &mut [Pixel], width: usize, height: usize, radius: usize) {
+    for idx_col in 0..width {
+        blur_line(
+            |idx| src[idx * width + idx_col],
+            |idx, pxl| dst[idx * width + idx_col] = pxl,
+            height,
+            radius,
+        );
+    }
+}
+
+fn blur_line(
+    get: impl Fn(usize) -> Pixel,
+    mut set: impl FnMut(usize, Pixel),
+    len: usize,
+    radius: usize,
+) {
+    if len == 0 {
+        return;
+    }
+    let at = |idx: isize| {
+        let pxl = get(idx.clamp(0, len as isize - 1) as usize);
+        [pxl.r as u32, pxl.g as u32, pxl.b as u32, pxl.a as u32]
+    };
+    let radius = radius as isize;
+    let count = 2 * radius as u32 + 1;
+    let mut sum = [0u32; 4];
+    for idx in -radius..=radius {
+        for (total, value) in sum.iter_mut().zip(at(idx)) {
+            *total += value;
+        }
+    }
+    for idx in 0..len as isize {
+        let average = sum.map(|total| ((total + count / 2) / count) as u8);
+        set(
+            idx as usize,
+            Pixel {
+                r: average[0],
+                g: average[1],
+                b: average[2],
+                a: average[3],
+            },
+        );
+        let (leaving, entering) = (at(idx - radius), at(idx + radius + 1));
+        for channel in 0..4 {
+            sum[channel] = sum[channel] + entering[channel] - leaving[channel];
+        }
+    }
+}
+
+/// Turns one of the demo effects on or off, see [Effect::demo]. Returns whether it's on now.
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_toggle_effect(renderer: *mut Renderer, id: u32) -> bool {
+    match Effect::demo(id) {
+        Some(effect) => (*renderer).effects_mut().toggle(effect),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIDTH: usize = 12;
+
+    fn frame(pxl: Pixel) -> Vec<Pixel> {
+        vec![pxl; WIDTH * 8]
+    }
+
+    fn apply(effect: Effect, pixels: &mut [Pixel]) {
+        let mut chain = EffectChain::new();
+        chain.push(effect);
+        chain.apply_pixels(pixels, WIDTH);
+    }
+
+    #[test]
+    fn uniform_frames_stay_uniform() {
+        let grey = Pixel::from_hex(0x607080);
+        for effect in [
+            Effect::Blur { radius: 0 },
+            Effect::Blur { radius: 3 },
+            Effect::Blur { radius: 20 },
+            Effect::Bloom {
+                threshold: 200,
+                radius: 2,
+                strength: 0.8,
+            },
+        ] {
+            let mut pixels = frame(grey);
+            apply(effect, &mut pixels);
+            assert_eq!(pixels, frame(grey), "{effect:?}");
+        }
+    }
+
+    #[test]
+    fn bloom_above_threshold() {
+        let mut pixels = frame(Pixel::from_hex(0x404040));
+        pixels[3 * WIDTH + 6] = Pixel::WHITE;
+        apply(
+            Effect::Bloom {
+                threshold: 200,
+                radius: 1,
+                strength: 1.0,
+            },
+            &mut pixels,
+        );
+        // the glow spreads to the neighbours, but no further
+        assert!(pixels[3 * WIDTH + 5].r > 0x40);
+        assert!(pixels[4 * WIDTH + 7].r > 0x40);
+        assert_eq!(pixels[3 * WIDTH + 8], Pixel::from_hex(0x404040));
+    }
+
+    #[test]
+    fn scanlines_darken_odd_rows() {
+        let grey = Pixel::from_hex(0x807060);
+        let mut pixels = frame(grey);
+        apply(Effect::Scanlines { darkness: 0.5 }, &mut pixels);
+        for (idx_row, row) in pixels.chunks_exact(WIDTH).enumerate() {
+            for pxl in row {
+                if idx_row % 2 == 0 {
+                    assert_eq!(*pxl, grey);
+                } else {
+                    assert!(pxl.r < grey.r && pxl.g < grey.g && pxl.b < grey.b);
+                    assert_eq!(pxl.a, grey.a);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn toggle() {
+        let trails = Effect::Trails { persistence: 0.8 };
+        let mut chain = EffectChain::new();
+        assert!(chain.toggle(trails));
+        assert_eq!(chain.effects, [trails]);
+        chain.apply_pixels(&mut frame(Pixel::WHITE), WIDTH);
+        assert!(!chain.previous.is_empty());
+
+        // whatever the settings, the same kind of effect turns it off
+        assert!(!chain.toggle(Effect::Trails { persistence: 0.2 }));
+        assert!(chain.is_empty());
+        assert!(chain.previous.is_empty());
+    }
+}
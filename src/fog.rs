@@ -0,0 +1,175 @@
+//! Depth cueing: edges fade out the further back they are, toward a fog colour or toward whatever's behind them,
+//! so the wireframe reads as 3D even without perspective. The depth is worked out for every pixel along each edge,
+//! from the `z` of its ends. Turn it on with [Renderer::set_fog].
+//!
+//! ```
+//! use meenle_noonle::fog::{Falloff, Fog};
+//!
+//! let fog = Fog::new(Falloff::Linear);
+//! // smaller z is nearer the viewer
+//! assert_eq!(fog.amount(fog.near), 0.0);
+//! assert_eq!(fog.amount((fog.near + fog.far) / 2.0), 0.5);
+//! assert_eq!(fog.amount(fog.far), 1.0);
+//! ```
+
+use crate::*;
+
+/// How quickly edges fade between [Fog::near] and [Fog::far].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Falloff {
+    /// Evenly, all the way to nothing at `far`.
+    #[default]
+    Linear,
+    /// Quickly at first, then more and more slowly, like real fog. 95% gone at `far`.
+    Exponential,
+}
+
+impl Falloff {
+    /// Picks a falloff by number, for the C exports: 0 is linear and 1 exponential.
+    pub fn from_id(id: u32) -> Option<Falloff> {
+        Some(match id {
+            0 => Falloff::Linear,
+            1 => Falloff::Exponential,
+            _ => return None,
+        })
+    }
+}
+
+/// Depth cue settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fog {
+    pub falloff: Falloff,
+    /// Depth where edges start fading. Nearer edges are drawn as they are.
+    pub near: f32,
+    /// Depth where edges have faded away, or almost, for [Falloff::Exponential].
+    pub far: f32,
+    /// What edges fade toward. [None] fades them into the background.
+    pub color: Option<Pixel>,
+}
+
+impl Fog {
+    /// Fog that suits the demo meshes, fading into the background.
+    pub fn new(falloff: Falloff) -> Fog {
+        Fog {
+            falloff,
+            near: -100.0,
+            far: 150.0,
+            color: None,
+        }
+    }
+
+    /// How faded an edge at depth `z` is, from 0 for not at all to 1 for gone.
+    pub fn amount(&self, z: f32) -> f32 {
+        let depth = ((z - self.near) / (self.far - self.near).max(f32::EPSILON)).max(0.0);
+        match self.falloff {
+            Falloff::Linear => depth.min(1.0),
+            // e^-3 is about 5%
+            Falloff::Exponential => 1.0 - (-3.0 * depth).exp(),
+        }
+    }
+}
+
+impl Mesh {
+    /// Draws the mesh's edges into the frame buffer `width` pixels wide, faded with depth. Edges are drawn from
+    /// the back forward, so nearer ones stay on top. Without a [Fog::color], they fade into `background`, which
+    /// must be the frame buffer's size; usually it's what the frame buffer was cleared to.
+    pub fn render_fogged<F: PixelFormat>(
+        &self,
+        frame_buffer: &mut FrameBuffer<F>,
+        background: &FrameBuffer<F>,
+        width: f32,
+        fog: &Fog,
+    ) {
+        let mut edges: Vec<[Vertex; 2]> = self
+            .tris
+            .iter()
+            .flat_map(|tri| {
+                let [v0, v1, v2] = tri.verts;
+                [[v0, v1], [v1, v2], [v2, v0]]
+            })
+            .collect();
+        edges.sort_by(|a, b| (b[0].z + b[1].z).total_cmp(&(a[0].z + a[1].z)));
+
+        // the middle of the pixel [centered] puts points in
+        let (dx, dy) = (
+            (frame_buffer.width() / 2) as f32 + 0.5,
+            (frame_buffer.height() / 2) as f32 + 0.5,
+        );
+        let mut target = Fogged {
+            frame_buffer,
+            background,
+            fog,
+            line: [[0.0; 3]; 2],
+        };
+        for [from, to] in edges {
+            target.line = [
+                [from.x + dx, from.y + dy, from.z],
+                [to.x + dx, to.y + dy, to.z],
+            ];
+            target.draw_thick_line(from.x, from.y, to.x, to.y, width, &Pixel::WHITE);
+        }
+    }
+}
+
+/// Draws one edge at a time into a frame buffer, fading each pixel by its depth along the edge.
+struct Fogged<'a, F: PixelFormat> {
+    frame_buffer: &'a mut FrameBuffer<F>,
+    /// What pixels fade into without a fog colour.
+    background: &'a FrameBuffer<F>,
+    fog: &'a Fog,
+    /// The edge being drawn, `[x, y, z]` at either end, in pixels from the top left corner.
+    line: [[f32; 3]; 2],
+}
+
+impl<F: PixelFormat> RenderTarget for Fogged<'_, F> {
+    fn size(&self) -> (usize, usize) {
+        self.frame_buffer.size()
+    }
+
+    fn plot_pixel(&mut self, x: i32, y: i32, pixel: &Pixel) {
+        if x < 0 || y < 0 {
+            return;
+        }
+        let Some(under) = self
+            .fog
+            .color
+            .or(self.background.pixel(x as usize, y as usize))
+        else {
+            return;
+        };
+        // how far along the edge the pixel is, for its depth
+        let [[x0, y0, z0], [x1, y1, z1]] = self.line;
+        let (along_x, along_y) = (x1 - x0, y1 - y0);
+        let len_squared = along_x * along_x + along_y * along_y;
+        let t = if len_squared < f32::EPSILON {
+            0.0
+        } else {
+            let (px, py) = (x as f32 + 0.5 - x0, y as f32 + 0.5 - y0);
+            ((px * along_x + py * along_y) / len_squared).clamp(0.0, 1.0)
+        };
+        let amount = self.fog.amount(z0 + (z1 - z0) * t);
+        self.frame_buffer
+            .plot_pixel(x, y, &pixel.lerp(under, amount));
+    }
+}
+
+/// Turns depth cueing on, with the falloff picked by [Falloff::from_id], fading toward a `0xRRGGBB` colour, or
+/// into the background for any `color` over `0xffffff`. Other falloffs turn it off.
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_set_fog(
+    renderer: *mut Renderer,
+    falloff: u32,
+    near: f32,
+    far: f32,
+    color: u32,
+) {
+    (*renderer).set_fog(Falloff::from_id(falloff).map(|falloff| Fog {
+        near,
+        far,
+        color: (color <= 0xffffff).then(|| Pixel::from_hex(color)),
+        ..Fog::new(falloff)
+    }));
+}
@@ -0,0 +1,198 @@
+//! A built-in 5x7 bitmap font covering printable ASCII, for drawing text like HUDs and labels straight into frame
+//! buffers, without a font library. Each glyph sits in a 6x8 cell, so there's a pixel of space after every
+//! character and under every line. Characters the font doesn't have are drawn as `?`.
+//!
+//! ```
+//! use meenle_noonle::{font, FrameBuffer, Pixel};
+//!
+//! let mut frame_buffer = FrameBuffer::new(100, 40);
+//! frame_buffer.draw_text(2, 2, "hi\nthere", Pixel::rgb(255, 255, 255));
+//! // the longest line is five characters, and there are two lines
+//! assert_eq!(font::text_size("hi\nthere"), (29, 15));
+//! ```
+
+#[cfg(target_arch = "powerpc")]
+use alloc::string::String;
+
+use crate::*;
+
+/// Width of a glyph, in pixels.
+pub const GLYPH_WIDTH: usize = 5;
+/// Height of a glyph, in pixels.
+pub const GLYPH_HEIGHT: usize = 7;
+/// How far along each character moves the next one.
+pub const ADVANCE: usize = GLYPH_WIDTH + 1;
+/// How far down each line moves the next one.
+pub const LINE_HEIGHT: usize = GLYPH_HEIGHT + 1;
+
+/// Glyphs for `' '` to `'~'`, a byte per column from left to right. Bit 0 is the top row.
+const GLYPHS: [[u8; GLYPH_WIDTH]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5f, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7f, 0x14, 0x7f, 0x14], // #
+    [0x24, 0x2a, 0x7f, 0x2a, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x55, 0x22, 0x50], // &
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '
+    [0x00, 0x1c, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1c, 0x00], // )
+    [0x14, 0x08, 0x3e, 0x08, 0x14], // *
+    [0x08, 0x08, 0x3e, 0x08, 0x08], // +
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x60, 0x60, 0x00, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3e, 0x51, 0x49, 0x45, 0x3e], // 0
+    [0x00, 0x42, 0x7f, 0x40, 0x00], // 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x45, 0x4b, 0x31], // 3
+    [0x18, 0x14, 0x12, 0x7f, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3c, 0x4a, 0x49, 0x49, 0x30], // 6
+    [0x01, 0x71, 0x09, 0x05, 0x03], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x06, 0x49, 0x49, 0x29, 0x1e], // 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], // :
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x51, 0x09, 0x06], // ?
+    [0x32, 0x49, 0x79, 0x41, 0x3e], // @
+    [0x7e, 0x11, 0x11, 0x11, 0x7e], // A
+    [0x7f, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3e, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7f, 0x41, 0x41, 0x22, 0x1c], // D
+    [0x7f, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7f, 0x09, 0x09, 0x09, 0x01], // F
+    [0x3e, 0x41, 0x49, 0x49, 0x7a], // G
+    [0x7f, 0x08, 0x08, 0x08, 0x7f], // H
+    [0x00, 0x41, 0x7f, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3f, 0x01], // J
+    [0x7f, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7f, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7f, 0x02, 0x0c, 0x02, 0x7f], // M
+    [0x7f, 0x04, 0x08, 0x10, 0x7f], // N
+    [0x3e, 0x41, 0x41, 0x41, 0x3e], // O
+    [0x7f, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3e, 0x41, 0x51, 0x21, 0x5e], // Q
+    [0x7f, 0x09, 0x19, 0x29, 0x46], // R
+    [0x46, 0x49, 0x49, 0x49, 0x31], // S
+    [0x01, 0x01, 0x7f, 0x01, 0x01], // T
+    [0x3f, 0x40, 0x40, 0x40, 0x3f], // U
+    [0x1f, 0x20, 0x40, 0x20, 0x1f], // V
+    [0x3f, 0x40, 0x38, 0x40, 0x3f], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x07, 0x08, 0x70, 0x08, 0x07], // Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], // Z
+    [0x00, 0x7f, 0x41, 0x41, 0x00], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // \
+    [0x00, 0x41, 0x41, 0x7f, 0x00], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x01, 0x02, 0x04, 0x00], // `
+    [0x20, 0x54, 0x54, 0x54, 0x78], // a
+    [0x7f, 0x48, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x20], // c
+    [0x38, 0x44, 0x44, 0x48, 0x7f], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x08, 0x7e, 0x09, 0x01, 0x02], // f
+    [0x0c, 0x52, 0x52, 0x52, 0x3e], // g
+    [0x7f, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7d, 0x40, 0x00], // i
+    [0x20, 0x40, 0x44, 0x3d, 0x00], // j
+    [0x7f, 0x10, 0x28, 0x44, 0x00], // k
+    [0x00, 0x41, 0x7f, 0x40, 0x00], // l
+    [0x7c, 0x04, 0x18, 0x04, 0x78], // m
+    [0x7c, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0x7c, 0x14, 0x14, 0x14, 0x08], // p
+    [0x08, 0x14, 0x14, 0x18, 0x7c], // q
+    [0x7c, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x20], // s
+    [0x04, 0x3f, 0x44, 0x40, 0x20], // t
+    [0x3c, 0x40, 0x40, 0x20, 0x7c], // u
+    [0x1c, 0x20, 0x40, 0x20, 0x1c], // v
+    [0x3c, 0x40, 0x30, 0x40, 0x3c], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x0c, 0x50, 0x50, 0x50, 0x3c], // y
+    [0x44, 0x64, 0x54, 0x4c, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x7f, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x10, 0x08, 0x08, 0x10, 0x08], // ~
+];
+
+/// The character's glyph, or `?`'s if the font doesn't have it.
+fn glyph(character: char) -> &'static [u8; GLYPH_WIDTH] {
+    match character {
+        ' '..='~' => &GLYPHS[character as usize - ' ' as usize],
+        _ => &GLYPHS['?' as usize - ' ' as usize],
+    }
+}
+
+/// Width and height of the text in pixels, as [draw_text] would draw it. Lines are split on `\n`.
+pub fn text_size(text: &str) -> (usize, usize) {
+    let (mut width, mut lines) = (0, 0);
+    for line in text.split('\n') {
+        width = width.max(line.chars().count() * ADVANCE);
+        lines += 1;
+    }
+    // no spacing after the last character or under the last line
+    (width.saturating_sub(1), lines * LINE_HEIGHT - 1)
+}
+
+/// Draws the text into the target, with the top left corner of the first character at pixel `(x, y)`. `\n`
+/// starts a new line back at `x`. Only the glyphs' pixels are drawn, over whatever's underneath.
+pub fn draw_text<T: RenderTarget + ?Sized>(
+    target: &mut T,
+    x: i32,
+    y: i32,
+    text: &str,
+    pixel: &Pixel,
+) {
+    for (idx_line, line) in text.split('\n').enumerate() {
+        let top = y + (idx_line * LINE_HEIGHT) as i32;
+        for (idx_char, character) in line.chars().enumerate() {
+            let left = x + (idx_char * ADVANCE) as i32;
+            for (idx_col, column) in glyph(character).iter().enumerate() {
+                for idx_row in 0..GLYPH_HEIGHT {
+                    if column >> idx_row & 1 != 0 {
+                        target.plot_pixel(left + idx_col as i32, top + idx_row as i32, pixel);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<F: PixelFormat> FrameBuffer<F> {
+    /// Draws text with the built-in font, see [draw_text].
+    pub fn draw_text(&mut self, x: i32, y: i32, text: &str, pixel: Pixel) {
+        draw_text(self, x, y, text, &pixel);
+    }
+}
+
+/// Draws `len` bytes of UTF-8 text into the frame buffer with the built-in font, in a `0xRRGGBB` colour, with its
+/// top left corner at pixel `(x, y)`. See [Renderer::draw_text].
+///
+/// # Safety
+/// `renderer` must come from [mn_create] and `text` must point to `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn mn_draw_text(
+    renderer: *mut Renderer,
+    x: i32,
+    y: i32,
+    text: *const u8,
+    len: u32,
+    color: u32,
+) {
+    if text.is_null() {
+        return;
+    }
+    let bytes = core::slice::from_raw_parts(text, len as usize);
+    // invalid UTF-8 comes out as replacement characters, drawn as ?
+    let text = String::from_utf8_lossy(bytes);
+    (*renderer).draw_text(x, y, &text, Pixel::from_hex(color));
+}
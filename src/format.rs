@@ -0,0 +1,166 @@
+//! Pixel formats frame buffers can be stored in. Everything is drawn with RGBA [Pixel]s, which the frame
+//! buffer's format packs into its bytes as they're plotted, so small screens can be rendered into natively
+//! without a conversion pass.
+
+use crate::dither::Palette;
+use crate::*;
+
+/// How pixels are packed into a frame buffer's bytes. Rows start on a byte boundary.
+pub trait PixelFormat: Copy + core::fmt::Debug {
+    /// Bits each pixel takes.
+    const BITS_PER_PIXEL: usize;
+
+    /// Writes the pixel into column `x` of the row.
+    fn encode(pixel: &Pixel, row: &mut [u8], x: usize);
+
+    /// Reads the pixel at column `x` of the row.
+    fn decode(row: &[u8], x: usize) -> Pixel;
+
+    /// Writes a whole row of pixels at once.
+    fn encode_row(pixels: &[Pixel], row: &mut [u8]) {
+        for (x, pixel) in pixels.iter().enumerate() {
+            Self::encode(pixel, row, x);
+        }
+    }
+
+    /// Bytes a row `width` pixels wide takes.
+    fn stride(width: usize) -> usize {
+        (width * Self::BITS_PER_PIXEL).div_ceil(8)
+    }
+
+    /// The colours the format stores, for dithering down to. [None] if it keeps 8 bits of every channel.
+    fn palette() -> Option<Palette> {
+        None
+    }
+}
+
+/// Brightness of the colour, 0 to 255, weighted the way BT.601 weighs red, green and blue.
+fn luma(pixel: &Pixel) -> u8 {
+    ((pixel.r as u32 * 77 + pixel.g as u32 * 150 + pixel.b as u32 * 29) >> 8) as u8
+}
+
+/// Red, green, blue and alpha bytes, as [Pixel] is laid out. The HTML canvas' format, and the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rgba8888;
+
+impl PixelFormat for Rgba8888 {
+    const BITS_PER_PIXEL: usize = 32;
+
+    fn encode(pixel: &Pixel, row: &mut [u8], x: usize) {
+        row[x * 4..x * 4 + 4].copy_from_slice(&[pixel.r, pixel.g, pixel.b, pixel.a]);
+    }
+
+    fn decode(row: &[u8], x: usize) -> Pixel {
+        Pixel {
+            r: row[x * 4],
+            g: row[x * 4 + 1],
+            b: row[x * 4 + 2],
+            a: row[x * 4 + 3],
+        }
+    }
+
+    fn encode_row(pixels: &[Pixel], row: &mut [u8]) {
+        for (bytes, pixel) in row.chunks_exact_mut(4).zip(pixels) {
+            bytes.copy_from_slice(&[pixel.r, pixel.g, pixel.b, pixel.a]);
+        }
+    }
+}
+
+/// Blue, green, red and alpha bytes, as many desktop surfaces and Windows bitmaps want.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bgra8888;
+
+impl PixelFormat for Bgra8888 {
+    const BITS_PER_PIXEL: usize = 32;
+
+    fn encode(pixel: &Pixel, row: &mut [u8], x: usize) {
+        row[x * 4..x * 4 + 4].copy_from_slice(&[pixel.b, pixel.g, pixel.r, pixel.a]);
+    }
+
+    fn decode(row: &[u8], x: usize) -> Pixel {
+        Pixel {
+            r: row[x * 4 + 2],
+            g: row[x * 4 + 1],
+            b: row[x * 4],
+            a: row[x * 4 + 3],
+        }
+    }
+}
+
+/// 16 bits a pixel, little-endian: 5 bits of red at the top, 6 of green and 5 of blue. No alpha.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rgb565;
+
+impl PixelFormat for Rgb565 {
+    const BITS_PER_PIXEL: usize = 16;
+
+    fn encode(pixel: &Pixel, row: &mut [u8], x: usize) {
+        let packed = (pixel.r as u16 >> 3) << 11 | (pixel.g as u16 >> 2) << 5 | pixel.b as u16 >> 3;
+        row[x * 2..x * 2 + 2].copy_from_slice(&packed.to_le_bytes());
+    }
+
+    fn decode(row: &[u8], x: usize) -> Pixel {
+        let packed = u16::from_le_bytes([row[x * 2], row[x * 2 + 1]]);
+        let (r, g, b) = (
+            (packed >> 11) as u8,
+            (packed >> 5) as u8 & 0x3f,
+            packed as u8 & 0x1f,
+        );
+        // repeat the top bits in the bottom ones, so full brightness stays full brightness
+        Pixel::rgb(r << 3 | r >> 2, g << 2 | g >> 4, b << 3 | b >> 2)
+    }
+
+    fn palette() -> Option<Palette> {
+        Some(Palette::RGB565)
+    }
+}
+
+/// One byte of brightness a pixel.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct L8;
+
+impl PixelFormat for L8 {
+    const BITS_PER_PIXEL: usize = 8;
+
+    fn encode(pixel: &Pixel, row: &mut [u8], x: usize) {
+        row[x] = luma(pixel);
+    }
+
+    fn decode(row: &[u8], x: usize) -> Pixel {
+        Pixel::rgb(row[x], row[x], row[x])
+    }
+
+    fn palette() -> Option<Palette> {
+        Some(Palette::Gray(256))
+    }
+}
+
+/// One bit a pixel, set for bright pixels and clear for dark ones, leftmost pixel in the top bit of each byte.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mono1;
+
+impl PixelFormat for Mono1 {
+    const BITS_PER_PIXEL: usize = 1;
+
+    fn encode(pixel: &Pixel, row: &mut [u8], x: usize) {
+        let bit = 0x80 >> (x % 8);
+        if luma(pixel) >= 128 {
+            row[x / 8] |= bit;
+        } else {
+            row[x / 8] &= !bit;
+        }
+    }
+
+    fn decode(row: &[u8], x: usize) -> Pixel {
+        let value = if row[x / 8] & (0x80 >> (x % 8)) != 0 {
+            255
+        } else {
+            0
+        };
+        Pixel::rgb(value, value, value)
+    }
+
+    fn palette() -> Option<Palette> {
+        Some(Palette::MONO)
+    }
+}
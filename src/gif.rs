@@ -0,0 +1,307 @@
+//! Animated GIF encoding. Every frame gets its own palette of up to 256 colours, picked by median cut, and only
+//! the area that changed since the frame before is stored, so wireframes over still backgrounds stay small.
+
+use crate::*;
+
+/// Colours are looked up with 5 bits per channel once there are too many to keep exactly.
+const CACHE_SIZE: usize = 1 << 15;
+const MAX_CODES: u16 = 4096;
+
+/// Builds a looping animated GIF one frame at a time.
+pub struct GifEncoder {
+    width: usize,
+    height: usize,
+    /// Frame delay, in hundredths of a second.
+    delay: u16,
+    out: Vec<u8>,
+    /// The frame before, to work out what changed.
+    previous: Option<FrameBuffer>,
+}
+
+impl GifEncoder {
+    /// Starts a `width` by `height` GIF playing at `fps` frames per second. GIFs time frames in hundredths of a
+    /// second, so the rate gets rounded.
+    pub fn new(width: usize, height: usize, fps: f32) -> GifEncoder {
+        let mut out = b"GIF89a".to_vec();
+        out.extend((width as u16).to_le_bytes());
+        out.extend((height as u16).to_le_bytes());
+        // no global colour table, every frame brings its own
+        out.extend([0, 0, 0]);
+        // loop forever
+        out.extend([0x21, 0xff, 11]);
+        out.extend(b"NETSCAPE2.0");
+        out.extend([3, 1, 0, 0, 0]);
+        GifEncoder {
+            width,
+            height,
+            delay: (100.0 / fps + 0.5).clamp(2.0, u16::MAX as f32) as u16,
+            out,
+            previous: None,
+        }
+    }
+
+    /// Adds a frame, which must be the size the GIF was started with. Alpha is ignored.
+    pub fn add_frame(&mut self, frame_buffer: &FrameBuffer) {
+        let full = Rect::full(self.width, self.height);
+        // unchanged frames still need a pixel to hang their delay on
+        let rect = match &self.previous {
+            Some(previous) => frame_buffer.changed_area(previous).unwrap_or(Rect {
+                width: 1,
+                height: 1,
+                ..full
+            }),
+            None => full,
+        };
+        let frame = frame_buffer.crop(rect);
+        let (palette, indices) = quantize(frame.pixels());
+        // the colour table's size is a power of two, at least 2
+        let table_bits = palette.len().next_power_of_two().trailing_zeros().max(1);
+
+        // graphic control extension: leave the frame in place for the next one to draw over, and wait
+        self.out.extend([0x21, 0xf9, 4, 0b0000_0100]);
+        self.out.extend(self.delay.to_le_bytes());
+        self.out.extend([0, 0]);
+
+        // image descriptor, with a local colour table
+        self.out.push(0x2c);
+        for num in [rect.x, rect.y, rect.width, rect.height] {
+            self.out.extend((num as u16).to_le_bytes());
+        }
+        self.out.push(0x80 | (table_bits - 1) as u8);
+        for idx in 0..1 << table_bits {
+            let color = palette.get(idx).copied().unwrap_or_default();
+            self.out.extend([color.r, color.g, color.b]);
+        }
+
+        let min_code_size = table_bits.max(2) as u8;
+        self.out.push(min_code_size);
+        for block in lzw(&indices, min_code_size).chunks(255) {
+            self.out.push(block.len() as u8);
+            self.out.extend(block);
+        }
+        self.out.push(0);
+        self.previous = Some(frame_buffer.clone());
+    }
+
+    /// Ends the GIF and returns the file.
+    pub fn finish(mut self) -> Vec<u8> {
+        self.out.push(0x3b);
+        self.out
+    }
+}
+
+/// Packs a colour into a number, red in the high bits, for sorting and searching.
+fn key(pixel: &Pixel) -> u32 {
+    (pixel.r as u32) << 16 | (pixel.g as u32) << 8 | pixel.b as u32
+}
+
+fn channel(color: u32, idx_channel: usize) -> u8 {
+    (color >> (16 - 8 * idx_channel)) as u8
+}
+
+/// Picks a palette of up to 256 colours for the pixels and returns it with every pixel's index into it. Images
+/// with few enough colours keep them exactly; the rest are median cut.
+fn quantize(pixels: &[Pixel]) -> (Vec<Pixel>, Vec<u8>) {
+    let mut colors: Vec<u32> = pixels.iter().map(key).collect();
+    colors.sort_unstable();
+    // every distinct colour and how many pixels have it
+    let mut histogram: Vec<(u32, u32)> = Vec::new();
+    for color in colors {
+        match histogram.last_mut() {
+            Some((last, count)) if *last == color => *count += 1,
+            _ => histogram.push((color, 1)),
+        }
+    }
+
+    if histogram.len() <= 256 {
+        let palette = histogram
+            .iter()
+            .map(|&(color, _)| Pixel::rgb(channel(color, 0), channel(color, 1), channel(color, 2)))
+            .collect();
+        let indices = pixels
+            .iter()
+            .map(|pxl| histogram.partition_point(|&(color, _)| color < key(pxl)) as u8)
+            .collect();
+        return (palette, indices);
+    }
+
+    let palette = median_cut(&mut histogram, 256);
+    let mut cache = vec![0u16; CACHE_SIZE];
+    let indices = pixels
+        .iter()
+        .map(|pxl| {
+            let idx_cache =
+                (pxl.r as usize >> 3) << 10 | (pxl.g as usize >> 3) << 5 | pxl.b as usize >> 3;
+            if cache[idx_cache] == 0 {
+                cache[idx_cache] = nearest(&palette, pxl) as u16 + 1;
+            }
+            (cache[idx_cache] - 1) as u8
+        })
+        .collect();
+    (palette, indices)
+}
+
+/// Splits the colours into `count` boxes, each time halving the box that spans the widest range of one channel
+/// at the median pixel, and gives the average colour of each box.
+fn median_cut(histogram: &mut [(u32, u32)], count: usize) -> Vec<Pixel> {
+    // a box's widest channel and how wide it is
+    let widest_channel = |colors: &[(u32, u32)]| {
+        (0..3)
+            .map(|idx_channel| {
+                let values = colors.iter().map(|&(color, _)| channel(color, idx_channel));
+                let spread = values.clone().max().unwrap_or(0) - values.min().unwrap_or(0);
+                (spread, idx_channel)
+            })
+            .max()
+            .unwrap_or((0, 0))
+    };
+    // boxes are ranges of the histogram, along with their widest channel
+    let mut boxes = Vec::with_capacity(count);
+    boxes.push((0..histogram.len(), widest_channel(histogram)));
+    while boxes.len() < count {
+        let widest = (0..boxes.len())
+            .filter(|&idx_box| boxes[idx_box].0.len() > 1)
+            .max_by_key(|&idx_box| boxes[idx_box].1 .0);
+        let Some(idx_box) = widest else {
+            break;
+        };
+        let (range, (_, idx_channel)) = boxes[idx_box].clone();
+        let colors = &mut histogram[range.clone()];
+        colors.sort_unstable_by_key(|&(color, _)| channel(color, idx_channel));
+        let total: u32 = colors.iter().map(|&(_, count)| count).sum();
+        let mut seen = 0;
+        let median = colors
+            .iter()
+            .position(|&(_, count)| {
+                seen += count;
+                seen * 2 >= total
+            })
+            .unwrap_or(0);
+        // both halves need at least one colour
+        let split = range.start + (median + 1).clamp(1, range.len() - 1);
+        boxes[idx_box] = (
+            range.start..split,
+            widest_channel(&histogram[range.start..split]),
+        );
+        boxes.push((
+            split..range.end,
+            widest_channel(&histogram[split..range.end]),
+        ));
+    }
+    boxes
+        .into_iter()
+        .map(|(range, _)| {
+            let colors = &histogram[range];
+            let total: u64 = colors.iter().map(|&(_, count)| count as u64).sum();
+            let average = |idx_channel| {
+                let sum: u64 = colors
+                    .iter()
+                    .map(|&(color, count)| channel(color, idx_channel) as u64 * count as u64)
+                    .sum();
+                ((sum + total / 2) / total.max(1)) as u8
+            };
+            Pixel::rgb(average(0), average(1), average(2))
+        })
+        .collect()
+}
+
+/// Index of the palette colour closest to the pixel.
+fn nearest(palette: &[Pixel], pixel: &Pixel) -> usize {
+    let distance = |color: &Pixel| {
+        let diff = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        diff(color.r, pixel.r) + diff(color.g, pixel.g) + diff(color.b, pixel.b)
+    };
+    (0..palette.len())
+        .min_by_key(|&idx| distance(&palette[idx]))
+        .unwrap_or(0)
+}
+
+/// Writes LZW codes least significant bit first, growing the code width as the table fills up.
+struct CodeWriter {
+    bytes: Vec<u8>,
+    bits: u32,
+    bit_count: u32,
+    width: u32,
+}
+
+impl CodeWriter {
+    fn write(&mut self, code: u16) {
+        self.bits |= (code as u32) << self.bit_count;
+        self.bit_count += self.width;
+        while self.bit_count >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+}
+
+/// Compresses palette indices with GIF's flavour of LZW.
+fn lzw(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+    let first_width = min_code_size as u32 + 1;
+    let mut out = CodeWriter {
+        bytes: Vec::new(),
+        bits: 0,
+        bit_count: 0,
+        width: first_width,
+    };
+    // open addressing hash table from (prefix code, next index) to code, twice the size of the code space
+    const TABLE_SIZE: usize = 2 * MAX_CODES as usize;
+    let mut keys = vec![u32::MAX; TABLE_SIZE];
+    let mut codes = vec![0u16; TABLE_SIZE];
+    let slot = |keys: &[u32], key: u32| {
+        let mut idx = (key.wrapping_mul(0x9e3779b1) >> 19) as usize;
+        while keys[idx] != u32::MAX && keys[idx] != key {
+            idx = (idx + 1) % TABLE_SIZE;
+        }
+        idx
+    };
+
+    out.write(clear);
+    let mut next_code = end + 1;
+    let Some((&first, rest)) = indices.split_first() else {
+        out.write(end);
+        return finish(out);
+    };
+    let mut prefix = first as u16;
+    for &index in rest {
+        let key = (prefix as u32) << 8 | index as u32;
+        let idx_slot = slot(&keys, key);
+        if keys[idx_slot] == key {
+            prefix = codes[idx_slot];
+            continue;
+        }
+        out.write(prefix);
+        if next_code < MAX_CODES {
+            // the decoder adds its entry a code late, so the width grows once the code after this one
+            // wouldn't fit
+            if next_code == 1 << out.width && out.width < 12 {
+                out.width += 1;
+            }
+            keys[idx_slot] = key;
+            codes[idx_slot] = next_code;
+            next_code += 1;
+        } else {
+            out.write(clear);
+            keys.fill(u32::MAX);
+            next_code = end + 1;
+            out.width = first_width;
+        }
+        prefix = index as u16;
+    }
+    out.write(prefix);
+    if next_code == 1 << out.width && out.width < 12 {
+        out.width += 1;
+    }
+    out.write(end);
+    finish(out)
+}
+
+fn finish(mut out: CodeWriter) -> Vec<u8> {
+    if out.bit_count > 0 {
+        out.bytes.push(out.bits as u8);
+    }
+    out.bytes
+}
@@ -1,33 +1,63 @@
 //! Meenle_Noonle is my software renderer demo, built to help me learn things I didn't previously know.
-//! It is a small Rust library with no dependencies, targeting wasm. It exports functions to act on an
-//! internal frame buffer ([render], [draw_line]), as well as a function to get a pointer to the frame buffer
-//! itself ([get_buffer]).
+//! It is a small Rust library with no dependencies, targeting wasm. All state lives in a [Renderer], which owns
+//! its frame buffer and acts on it ([Renderer::render], [Renderer::draw_line]). Renderers don't share anything,
+//! so several can be used from different threads at once.
+//!
+//! From C and wasm, renderers are handles: create one with [mn_create], pass it to the other `mn_` functions
+//! ([mn_render], [mn_draw_line], [mn_get_buffer] to get a pointer to the frame buffer...), and free it with
+//! [mn_destroy].
 
 #![cfg_attr(target_arch = "powerpc", no_std)]
+#[cfg(all(target_arch = "powerpc", feature = "parallel"))]
+compile_error!("the `parallel` feature needs std threads, which aren't available on powerpc");
 #[cfg(target_arch = "powerpc")]
 extern crate alloc;
 #[cfg(target_arch = "powerpc")]
-use {alloc::vec, alloc::vec::Vec, rs_ppc_support::MSLmaths};
+use {alloc::boxed::Box, alloc::vec, alloc::vec::Vec, rs_ppc_support::MSLmaths};
 
-use core::ops::Mul;
+use core::marker::PhantomData;
+use core::ops::{Add, Mul, Sub};
+pub mod background;
+pub mod camera;
+pub mod deflate;
 pub mod demo;
+pub mod dither;
+pub mod effects;
+pub mod fog;
+pub mod font;
+pub mod format;
+pub mod gif;
 pub mod meshes;
+pub mod model;
+#[cfg(feature = "parallel")]
+mod parallel;
+pub mod pick;
+pub mod png;
+pub mod ppm;
+pub mod primitives;
+pub mod scale;
+pub mod skinning;
+pub mod stereo;
+pub mod svg;
+pub mod target;
+pub mod transform;
+pub mod yuyv;
+
+use background::Background;
+use camera::OrbitController;
+use dither::Dither;
+use effects::EffectChain;
+use fog::Fog;
+pub use format::PixelFormat;
+use format::Rgba8888;
+use skinning::{Skeleton, SkinnedMesh};
+use stereo::Stereo;
+pub use target::RenderTarget;
 
 // dimensions for the canvas
 pub const WIDTH: usize = 500;
 pub const HEIGHT: usize = 500;
 
-/// Frame buffer.
-static mut BUFFER: [[Pixel; WIDTH]; HEIGHT] = [[Pixel {
-    r: 0,
-    g: 0,
-    b: 0,
-    a: 0,
-}; WIDTH]; HEIGHT];
-
-/// Frame buffer with the pretty background pattern. Used to clear the scene.
-static mut BG_BUFFER: [[Pixel; WIDTH]; HEIGHT] = unsafe { BUFFER };
-
 #[derive(Clone, Copy)]
 pub enum Axis {
     X,
@@ -47,7 +77,7 @@ pub type Vertex = Vec3;
 /// Pixel for the frame buffer. RGBA color, to match HTML canvas' buffer format.
 #[repr(C)]
 #[allow(dead_code)]
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct Pixel {
     pub r: u8,
     pub g: u8,
@@ -68,6 +98,28 @@ impl Pixel {
         b: 255,
         a: 255,
     };
+
+    /// Opaque colour.
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Pixel {
+        Pixel { r, g, b, a: 255 }
+    }
+
+    /// Opaque colour from a `0xRRGGBB` number, as used by the C exports.
+    pub const fn from_hex(hex: u32) -> Pixel {
+        Pixel::rgb((hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
+    }
+
+    /// Mixes in `t` of the other colour, `t` being clamped to 0..=1.
+    pub fn lerp(self, other: Pixel, t: f32) -> Pixel {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t + 0.5) as u8;
+        Pixel {
+            r: mix(self.r, other.r),
+            g: mix(self.g, other.g),
+            b: mix(self.b, other.b),
+            a: mix(self.a, other.a),
+        }
+    }
 }
 
 impl Mul<Vec3> for f32 {
@@ -78,6 +130,22 @@ impl Mul<Vec3> for f32 {
     }
 }
 
+impl Add for Vec3 {
+    type Output = Vec3;
+
+    fn add(self, rhs: Vec3) -> Self::Output {
+        Vec3::from([self.x + rhs.x, self.y + rhs.y, self.z + rhs.z])
+    }
+}
+
+impl Sub for Vec3 {
+    type Output = Vec3;
+
+    fn sub(self, rhs: Vec3) -> Self::Output {
+        Vec3::from([self.x - rhs.x, self.y - rhs.y, self.z - rhs.z])
+    }
+}
+
 impl From<[f32; 3]> for Vec3 {
     fn from(nums: [f32; 3]) -> Self {
         Vec3 {
@@ -89,6 +157,7 @@ impl From<[f32; 3]> for Vec3 {
 }
 
 /// Simple 3x3 Matrix for graphics maths.
+#[derive(Debug, Clone, Copy)]
 pub struct Mat3x3 {
     pub mat: [[f32; 3]; 3],
 }
@@ -141,6 +210,22 @@ impl Mul<Vec3> for Mat3x3 {
     }
 }
 
+impl Mul for Mat3x3 {
+    type Output = Mat3x3;
+
+    fn mul(self, rhs: Mat3x3) -> Self::Output {
+        let mut mat = [[0.0; 3]; 3];
+        for (idx_row, row) in mat.iter_mut().enumerate() {
+            for (idx_col, cell) in row.iter_mut().enumerate() {
+                *cell = self.mat[idx_row][0] * rhs.mat[0][idx_col]
+                    + self.mat[idx_row][1] * rhs.mat[1][idx_col]
+                    + self.mat[idx_row][2] * rhs.mat[2][idx_col];
+            }
+        }
+        Mat3x3 { mat }
+    }
+}
+
 ///Triangle.
 #[derive(Debug, Copy, Clone)]
 #[repr(C)]
@@ -149,32 +234,21 @@ pub struct Tri {
 }
 
 impl Tri {
-    /// Draws itself into the framebuffer.
-    fn render(&self) {
-        const DEPTH_RANGE: f32 = 0.0;
-        const MODEL_Z_RANGE: f32 = 50.0;
-        let stereoscopy_offset = |idx_vtx: usize| -> f32 {
-            (-2.0 * DEPTH_RANGE) * (self.verts[idx_vtx].z + MODEL_Z_RANGE) / (2.0 * MODEL_Z_RANGE)
-                - DEPTH_RANGE
-        };
-        draw_line(
-            self.verts[0].x + stereoscopy_offset(0),
-            self.verts[0].y,
-            self.verts[1].x + stereoscopy_offset(1),
-            self.verts[1].y,
-        );
-        draw_line(
-            self.verts[1].x + stereoscopy_offset(1),
-            self.verts[1].y,
-            self.verts[2].x + stereoscopy_offset(2),
-            self.verts[2].y,
-        );
-        draw_line(
-            self.verts[2].x + stereoscopy_offset(2),
-            self.verts[2].y,
-            self.verts[0].x + stereoscopy_offset(0),
-            self.verts[0].y,
-        );
+    /// The triangle's three edges as lines, `[x0, y0, x1, y1]`, ready to be drawn into the frame buffer.
+    fn edges(&self) -> [[f32; 4]; 3] {
+        let [v0, v1, v2] = self.verts;
+        [
+            [v0.x, v0.y, v1.x, v1.y],
+            [v1.x, v1.y, v2.x, v2.y],
+            [v2.x, v2.y, v0.x, v0.y],
+        ]
+    }
+
+    /// Draws its edges into the target, `width` pixels wide.
+    fn render_to<T: RenderTarget + ?Sized>(&self, target: &mut T, width: f32) {
+        for [x0, y0, x1, y1] in self.edges() {
+            target.draw_thick_line(x0, y0, x1, y1, width, &Pixel::WHITE);
+        }
     }
 }
 
@@ -230,48 +304,273 @@ impl Mesh {
 
     /// Scales the mesh by the given scalar.
     pub fn scale(&mut self, scalar: f32) {
-        for tri in &mut self.tris {
-            for vert in &mut tri.verts {
-                *vert = Mat3x3::identity(scalar) * *vert
-            }
-        }
+        transform::transform(&Mat3x3::identity(scalar), self.verts_mut());
     }
 
     /// Rotates the mesh.
     pub fn rot(&mut self, axis: Axis, angle: f32) {
-        for tri in &mut self.tris {
-            for vert in &mut tri.verts {
-                *vert = Mat3x3::rot(angle, axis) * *vert;
-            }
+        transform::transform(&Mat3x3::rot(angle, axis), self.verts_mut());
+    }
+
+    /// Moves every vertex of the mesh by the offset.
+    pub fn translate(&mut self, offset: Vec3) {
+        for vert in self.verts_mut() {
+            *vert = *vert + offset;
+        }
+    }
+
+    /// Every vertex of every triangle, in order.
+    pub fn verts_mut(&mut self) -> &mut [Vertex] {
+        // SAFETY: Tri is repr(C) and holds nothing but its three vertices
+        unsafe {
+            core::slice::from_raw_parts_mut(self.tris.as_mut_ptr().cast(), self.tris.len() * 3)
         }
     }
 
-    /// Draws the mesh into the framebuffer.
-    fn render(&self) {
+    /// Draws the mesh's edges into the target, each through [RenderTarget::draw_line].
+    pub fn render_to<T: RenderTarget + ?Sized>(&self, target: &mut T) {
+        for [x0, y0, x1, y1] in self.tris.iter().flat_map(|tri| tri.edges()) {
+            target.draw_line(x0, y0, x1, y1, &Pixel::WHITE);
+        }
+    }
+
+    /// Draws the mesh's edges into the target `width` pixels wide, each through [RenderTarget::draw_thick_line], so
+    /// edges meeting at a vertex join up without gaps.
+    pub fn render_with_width<T: RenderTarget + ?Sized>(&self, target: &mut T, width: f32) {
         for tri in &self.tris {
-            tri.render();
+            tri.render_to(target, width);
         }
     }
 }
 
-/// Plots a single pixel into the frame buffer.
-fn plot_pixel(x: usize, y: usize, pixel: &Pixel) {
-    unsafe {
-        *BUFFER
-            .get_mut(y)
-            .unwrap_or(&mut BUFFER[0])
-            .get_mut(x)
-            .unwrap_or(&mut BUFFER[0][0]) = *pixel;
+/// Rectangle of pixels.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    /// Rectangle covering a whole `width` by `height` image.
+    pub const fn full(width: usize, height: usize) -> Rect {
+        Rect {
+            x: 0,
+            y: 0,
+            width: width as u32,
+            height: height as u32,
+        }
+    }
+
+    /// Smallest rectangle containing both.
+    pub fn union(self, other: Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Rect {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+
+    /// The rows the rectangle spans.
+    pub fn rows(&self) -> core::ops::Range<usize> {
+        self.y as usize..(self.y + self.height) as usize
+    }
+
+    /// The columns the rectangle spans.
+    pub fn cols(&self) -> core::ops::Range<usize> {
+        self.x as usize..(self.x + self.width) as usize
     }
 }
 
-/// Uses Bresenham's algorithm to draw a line.
-#[no_mangle]
-pub extern "C" fn draw_line(x0: f32, y0: f32, x1: f32, y1: f32) {
-    let mut x0 = x0 as i32 + (WIDTH / 2) as i32;
-    let mut y0 = y0 as i32 + (WIDTH / 2) as i32;
-    let mut x1 = x1 as i32 + (WIDTH / 2) as i32;
-    let mut y1 = y1 as i32 + (WIDTH / 2) as i32;
+/// Union of two optional rectangles, [None] being empty.
+fn union(rect1: Option<Rect>, rect2: Option<Rect>) -> Option<Rect> {
+    match (rect1, rect2) {
+        (Some(rect1), Some(rect2)) => Some(rect1.union(rect2)),
+        (rect, None) | (None, rect) => rect,
+    }
+}
+
+/// Image stored row by row, with its pixels packed in a [PixelFormat]: RGBA [Pixel]s unless asked otherwise.
+#[derive(Debug, Clone)]
+pub struct FrameBuffer<F: PixelFormat = Rgba8888> {
+    width: usize,
+    height: usize,
+    /// The rows, each [PixelFormat::stride] bytes long.
+    bytes: Vec<u8>,
+    /// Bounding rectangle of everything drawn since the last [FrameBuffer::take_drawn].
+    drawn: Option<Rect>,
+    format: PhantomData<F>,
+}
+
+impl FrameBuffer {
+    /// Creates an RGBA frame buffer filled with transparent black.
+    pub fn new(width: usize, height: usize) -> FrameBuffer {
+        FrameBuffer::with_format(width, height)
+    }
+
+    pub fn pixels(&self) -> &[Pixel] {
+        // SAFETY: Pixel is repr(C) and holds nothing but its four bytes, so RGBA rows are pixels
+        unsafe { core::slice::from_raw_parts(self.bytes.as_ptr().cast(), self.bytes.len() / 4) }
+    }
+
+    /// Direct access to the pixels. Since there's no telling what gets changed, the whole frame buffer counts as
+    /// drawn.
+    pub fn pixels_mut(&mut self) -> &mut [Pixel] {
+        self.drawn = Some(Rect::full(self.width, self.height));
+        self.pixels_untracked()
+    }
+
+    /// The pixels, without counting anything as drawn.
+    fn pixels_untracked(&mut self) -> &mut [Pixel] {
+        // SAFETY: as in FrameBuffer::pixels
+        unsafe {
+            core::slice::from_raw_parts_mut(self.bytes.as_mut_ptr().cast(), self.bytes.len() / 4)
+        }
+    }
+
+    /// Copies the part of the frame buffer inside the rectangle, which must fit inside it.
+    pub fn crop(&self, rect: Rect) -> FrameBuffer {
+        let mut cropped = FrameBuffer::new(rect.width as usize, rect.height as usize);
+        let cropped_width = cropped.width;
+        for (idx_row, row) in rect.rows().enumerate() {
+            let cols = row * self.width + rect.x as usize..row * self.width + rect.cols().end;
+            cropped.pixels_untracked()[idx_row * cropped_width..(idx_row + 1) * cropped_width]
+                .copy_from_slice(&self.pixels()[cols]);
+        }
+        cropped
+    }
+
+    /// Bounding rectangle of the pixels that differ from the other frame buffer, which must be the same size.
+    /// [None] if they're identical.
+    pub fn changed_area(&self, other: &FrameBuffer) -> Option<Rect> {
+        let mut changed = None;
+        let rows = self.pixels().chunks_exact(self.width.max(1));
+        for (idx_row, (row, other_row)) in rows
+            .zip(other.pixels().chunks_exact(self.width.max(1)))
+            .enumerate()
+        {
+            let Some(left) = row.iter().zip(other_row).position(|(a, b)| a != b) else {
+                continue;
+            };
+            let right = row
+                .iter()
+                .zip(other_row)
+                .rposition(|(a, b)| a != b)
+                .unwrap_or(left);
+            changed = union(
+                changed,
+                Some(Rect {
+                    x: left as u32,
+                    y: idx_row as u32,
+                    width: (right - left + 1) as u32,
+                    height: 1,
+                }),
+            );
+        }
+        changed
+    }
+}
+
+impl<F: PixelFormat> FrameBuffer<F> {
+    /// Creates a frame buffer in any pixel format, with all its bytes zero: black, and transparent if the format
+    /// has alpha.
+    pub fn with_format(width: usize, height: usize) -> FrameBuffer<F> {
+        FrameBuffer {
+            width,
+            height,
+            bytes: vec![0; F::stride(width) * height],
+            drawn: None,
+            format: PhantomData,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Bytes in each row.
+    pub fn stride(&self) -> usize {
+        F::stride(self.width)
+    }
+
+    /// The pixels, packed in the frame buffer's format.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Direct access to the packed pixels. Like [FrameBuffer::pixels_mut], it counts the whole frame buffer as
+    /// drawn.
+    pub fn bytes_mut(&mut self) -> &mut [u8] {
+        self.drawn = Some(Rect::full(self.width, self.height));
+        &mut self.bytes
+    }
+
+    /// Reads a single pixel. [None] outside the frame buffer.
+    pub fn pixel(&self, x: usize, y: usize) -> Option<Pixel> {
+        (x < self.width && y < self.height).then(|| {
+            let stride = self.stride();
+            F::decode(&self.bytes[y * stride..(y + 1) * stride], x)
+        })
+    }
+
+    /// Copies the frame buffer into another pixel format.
+    pub fn convert<G: PixelFormat>(&self) -> FrameBuffer<G> {
+        let mut converted = FrameBuffer::with_format(self.width, self.height);
+        let (stride, converted_stride) = (self.stride(), converted.stride());
+        let mut pixels = vec![Pixel::default(); self.width];
+        for idx_row in 0..self.height {
+            let row = &self.bytes[idx_row * stride..(idx_row + 1) * stride];
+            for (x, pixel) in pixels.iter_mut().enumerate() {
+                *pixel = F::decode(row, x);
+            }
+            G::encode_row(
+                &pixels,
+                &mut converted.bytes[idx_row * converted_stride..(idx_row + 1) * converted_stride],
+            );
+        }
+        converted.drawn = self.drawn;
+        converted
+    }
+
+    /// Grows the drawn area to include the rectangle.
+    pub fn mark_drawn(&mut self, rect: Rect) {
+        self.drawn = union(self.drawn, Some(rect));
+    }
+
+    /// Bounding rectangle of everything drawn since the last [FrameBuffer::take_drawn].
+    pub fn drawn(&self) -> Option<Rect> {
+        self.drawn
+    }
+
+    /// Returns the drawn area and starts tracking it afresh.
+    pub fn take_drawn(&mut self) -> Option<Rect> {
+        self.drawn.take()
+    }
+}
+
+/// Converts a line relative to the centre of a `(width, height)` image to pixel coordinates.
+fn centered((width, height): (usize, usize), [x0, y0, x1, y1]: [f32; 4]) -> [i32; 4] {
+    let (half_width, half_height) = ((width / 2) as i32, (height / 2) as i32);
+    [
+        x0 as i32 + half_width,
+        y0 as i32 + half_height,
+        x1 as i32 + half_width,
+        y1 as i32 + half_height,
+    ]
+}
+
+/// Uses Bresenham's algorithm to walk the line between two pixels, calling `plot` on every pixel along it.
+fn bresenham(x0: i32, y0: i32, x1: i32, y1: i32, mut plot: impl FnMut(i32, i32)) {
+    let (mut x0, mut y0, mut x1, mut y1) = (x0, y0, x1, y1);
 
     let steep = (y1 - y0).abs() > (x1 - x0).abs();
     if steep {
@@ -291,9 +590,9 @@ pub extern "C" fn draw_line(x0: f32, y0: f32, x1: f32, y1: f32) {
 
     for x in x0..=x1 {
         if steep {
-            plot_pixel(y as usize, x as usize, &Pixel::WHITE);
+            plot(y, x);
         } else {
-            plot_pixel(x as usize, y as usize, &Pixel::WHITE);
+            plot(x, y);
         }
         error -= dy;
         if error < 0 {
@@ -303,49 +602,356 @@ pub extern "C" fn draw_line(x0: f32, y0: f32, x1: f32, y1: f32) {
     }
 }
 
-/// Generates the pretty background pattern.
-#[no_mangle]
-pub extern "C" fn generate_background() {
-    unsafe {
-        for (idx_row, row) in BG_BUFFER.iter_mut().enumerate() {
-            for (idx_col, pxl) in row.iter_mut().enumerate() {
-                *pxl = Pixel {
-                    r: ((255.0 / HEIGHT as f32) * idx_row as f32) as u8,
-                    g: ((255.0 / WIDTH as f32) * idx_col as f32) as u8,
-                    b: ((-(255.0 / HEIGHT as f32) * idx_row as f32) + 255.0) as u8,
-                    a: 255,
-                };
+/// Owns everything needed to render: the frame buffer, the background used to clear it, and the demo state.
+#[derive(Debug, Clone)]
+pub struct Renderer<F: PixelFormat = Rgba8888> {
+    buffer: FrameBuffer<F>,
+    /// What the frame buffer gets cleared to.
+    background: Background,
+    /// Frame buffer with the background drawn in. Used to clear the scene.
+    background_buffer: FrameBuffer<F>,
+    demo_mesh: Option<Mesh>,
+    /// Set for animated demo meshes. Posed into `demo_mesh` every frame.
+    demo_skin: Option<(SkinnedMesh, Skeleton)>,
+    /// The demo mesh as [Renderer::render_spin] or [Renderer::render_view] last drew it, turned, for picking.
+    spun_mesh: Option<Mesh>,
+    /// Camera for [Renderer::render_view].
+    view: OrbitController,
+    /// Area restored from the background by the last [Renderer::fill_buffer].
+    cleared: Option<Rect>,
+    /// The background changed, so the next [Renderer::fill_buffer] has to restore all of it.
+    background_changed: bool,
+    /// How the background is dithered, for formats with few colours.
+    dither: Option<Dither>,
+    /// Run over every frame once the mesh is drawn.
+    effects: EffectChain,
+    /// Draws the mesh once for each eye when set.
+    stereo: Option<Stereo>,
+    /// Width of the mesh's edges, in pixels.
+    line_width: f32,
+    /// Fades the mesh's edges with depth when set.
+    fog: Option<Fog>,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Renderer::new()
+    }
+}
+
+impl Renderer {
+    /// Creates a [WIDTH] by [HEIGHT] renderer with a blank background and no demo mesh.
+    pub fn new() -> Renderer {
+        Renderer::with_size(WIDTH, HEIGHT)
+    }
+
+    /// Creates a renderer of any size, with a blank background and no demo mesh.
+    pub fn with_size(width: usize, height: usize) -> Renderer {
+        Renderer::with_format(width, height)
+    }
+}
+
+impl<F: PixelFormat> Renderer<F> {
+    /// Creates a renderer that draws into a frame buffer of any pixel format.
+    pub fn with_format(width: usize, height: usize) -> Renderer<F> {
+        Renderer {
+            buffer: FrameBuffer::with_format(width, height),
+            background: Background::default(),
+            background_buffer: FrameBuffer::with_format(width, height),
+            demo_mesh: None,
+            spun_mesh: None,
+            view: OrbitController::new(),
+            demo_skin: None,
+            cleared: None,
+            background_changed: true,
+            dither: None,
+            effects: EffectChain::new(),
+            stereo: None,
+            line_width: 1.0,
+            fog: None,
+        }
+    }
+
+    /// The frame buffer everything is rendered into.
+    pub fn buffer(&self) -> &FrameBuffer<F> {
+        &self.buffer
+    }
+
+    pub fn buffer_mut(&mut self) -> &mut FrameBuffer<F> {
+        &mut self.buffer
+    }
+
+    /// Changes the size of the frame buffer, say to follow a window as it's resized. The background is redrawn at
+    /// the new size and the next frame is drawn in full. Does nothing if the size hasn't changed.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        if (width, height) == (self.buffer.width, self.buffer.height) {
+            return;
+        }
+        self.buffer = FrameBuffer::with_format(width, height);
+        self.background_buffer = FrameBuffer::with_format(width, height);
+        self.cleared = None;
+        self.generate_background();
+    }
+
+    /// Generates the background pattern, the pretty gradient unless [Renderer::set_background] picked another.
+    pub fn generate_background(&mut self) {
+        self.background
+            .draw_dithered(&mut self.background_buffer, 0.0, self.dither);
+        self.background_changed = true;
+    }
+
+    /// Switches to another background.
+    pub fn set_background(&mut self, background: Background) {
+        self.background = background;
+        self.generate_background();
+    }
+
+    /// Dithers the background down to the colours the frame buffer's format stores, so gradients don't band.
+    /// Only formats with a [PixelFormat::palette] are dithered; [None] turns it off.
+    pub fn set_dither(&mut self, dither: Option<Dither>) {
+        self.dither = dither;
+        self.generate_background();
+    }
+
+    /// Redraws the background as it looks at `time_seconds`, if it's animated.
+    pub fn animate_background(&mut self, time_seconds: f32) {
+        if self.background.is_animated() {
+            self.background
+                .draw_dithered(&mut self.background_buffer, time_seconds, self.dither);
+            self.background_changed = true;
+        }
+    }
+
+    /// Fills the frame buffer with a pretty pattern. Only the area drawn over since the last call is restored
+    /// from the background, unless the background itself changed.
+    pub fn fill_buffer(&mut self) {
+        let drawn = self.buffer.take_drawn();
+        if self.background_changed {
+            self.buffer
+                .bytes
+                .copy_from_slice(&self.background_buffer.bytes);
+            self.cleared = Some(Rect::full(self.buffer.width, self.buffer.height));
+            self.background_changed = false;
+        } else {
+            if let Some(drawn) = drawn {
+                let stride = self.buffer.stride();
+                // formats under a byte a pixel share bytes between columns, so copy whole bytes
+                let start = drawn.x as usize * F::BITS_PER_PIXEL / 8;
+                let end = (drawn.cols().end * F::BITS_PER_PIXEL).div_ceil(8);
+                for row in drawn.rows() {
+                    let cols = row * stride + start..row * stride + end;
+                    self.buffer.bytes[cols.clone()]
+                        .copy_from_slice(&self.background_buffer.bytes[cols]);
+                }
             }
+            self.cleared = drawn;
         }
     }
+
+    /// The area of the frame buffer that changed since the frame before: what the last [Renderer::fill_buffer]
+    /// cleared plus everything drawn since. Front-ends only need to copy these pixels to the screen. [None] when
+    /// nothing changed. With the `parallel` feature, it can be a few pixels bigger than what was really drawn.
+    pub fn dirty_rect(&self) -> Option<Rect> {
+        union(self.cleared, self.buffer.drawn)
+    }
+
+    /// Draws a white line into the frame buffer. See [RenderTarget::draw_line].
+    pub fn draw_line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32) {
+        self.buffer.draw_line(x0, y0, x1, y1, &Pixel::WHITE);
+    }
+
+    /// Draws text with the built-in font, its top left corner at pixel `(x, y)`. See [font::draw_text].
+    pub fn draw_text(&mut self, x: i32, y: i32, text: &str, pixel: Pixel) {
+        self.buffer.draw_text(x, y, text, pixel);
+    }
+
+    /// The effects run over every frame after the mesh is drawn.
+    pub fn effects(&self) -> &EffectChain {
+        &self.effects
+    }
+
+    pub fn effects_mut(&mut self) -> &mut EffectChain {
+        &mut self.effects
+    }
+
+    /// Draws meshes once for each eye, see [stereo]. [None] goes back to drawing them once.
+    pub fn set_stereo(&mut self, stereo: Option<Stereo>) {
+        self.stereo = stereo;
+    }
+
+    pub fn stereo(&self) -> Option<Stereo> {
+        self.stereo
+    }
+
+    /// Draws meshes' edges `width` pixels wide, see [Mesh::render_with_width]. Thicker lines hold up better on TVs
+    /// and high-DPI screens. Starts at 1.
+    pub fn set_line_width(&mut self, width: f32) {
+        self.line_width = width;
+    }
+
+    pub fn line_width(&self) -> f32 {
+        self.line_width
+    }
+
+    /// Fades meshes' edges the further back they are, see [fog]. [None] turns it off. Stereo rendering isn't
+    /// faded.
+    pub fn set_fog(&mut self, fog: Option<Fog>) {
+        self.fog = fog;
+    }
+
+    pub fn fog(&self) -> Option<Fog> {
+        self.fog
+    }
+
+    /// Clears the frame buffer, draws the mesh into it and runs the effects over it. With the `parallel`
+    /// feature, the mesh is drawn on several threads, unless it's drawn in stereo or with fog, which are always
+    /// drawn on the calling thread.
+    pub fn render_mesh(&mut self, mesh: &Mesh) {
+        self.fill_buffer();
+        if let Some(stereo) = self.stereo {
+            stereo.render_to(mesh, &mut self.buffer, self.line_width);
+        } else if let Some(ref fog) = self.fog {
+            mesh.render_fogged(
+                &mut self.buffer,
+                &self.background_buffer,
+                self.line_width,
+                fog,
+            );
+        } else {
+            #[cfg(feature = "parallel")]
+            parallel::render_mesh(mesh, &mut self.buffer, self.line_width);
+            #[cfg(not(feature = "parallel"))]
+            mesh.render_with_width(&mut self.buffer, self.line_width);
+        }
+        self.effects.apply(&mut self.buffer);
+    }
+
+    /// Renders the demo into the frame buffer.
+    pub fn render(&mut self, scalar: f32, x_angle: f32, y_angle: f32, z_angle: f32) {
+        let mut demo_mesh = meshes::monkey();
+        demo_mesh.scale(50.0);
+
+        demo_mesh.scale(scalar);
+
+        demo_mesh.rot(Axis::X, x_angle);
+        demo_mesh.rot(Axis::Y, y_angle);
+        demo_mesh.rot(Axis::Z, z_angle);
+
+        self.render_mesh(&demo_mesh);
+    }
 }
 
-/// Fills the frame buffer with a pretty pattern.
+/// Creates a renderer. Free it with [mn_destroy].
+#[no_mangle]
+pub extern "C" fn mn_create() -> *mut Renderer {
+    Box::into_raw(Box::new(Renderer::new()))
+}
+
+/// Frees a renderer created by [mn_create].
+///
+/// # Safety
+/// `renderer` must come from [mn_create] and must not be used afterwards.
 #[no_mangle]
-pub extern "C" fn fill_buffer() {
-    unsafe {
-        BUFFER = BG_BUFFER;
+pub unsafe extern "C" fn mn_destroy(renderer: *mut Renderer) {
+    if !renderer.is_null() {
+        drop(Box::from_raw(renderer));
     }
 }
 
-/// Gets a pointer to the frame buffer.
+/// Allocates `len` zeroed bytes, for callers that can't allocate memory the library can read, like JavaScript
+/// passing text to [font::mn_draw_text]. Free it with [mn_free].
 #[no_mangle]
-pub extern "C" fn get_buffer() -> &'static [[Pixel; WIDTH]; HEIGHT] {
-    unsafe { &BUFFER }
+pub extern "C" fn mn_alloc(len: u32) -> *mut u8 {
+    Box::into_raw(vec![0u8; len as usize].into_boxed_slice()).cast()
 }
 
-/// Renders the demo into the frame buffer.
+/// Frees bytes from [mn_alloc].
+///
+/// # Safety
+/// `ptr` must come from [mn_alloc], called with the same `len`, and must not be used afterwards.
 #[no_mangle]
-pub extern "C" fn render(scalar: f32, x_angle: f32, y_angle: f32, z_angle: f32) {
-    let mut demo_mesh = meshes::monkey();
-    demo_mesh.scale(50.0);
+pub unsafe extern "C" fn mn_free(ptr: *mut u8, len: u32) {
+    if !ptr.is_null() {
+        drop(Box::from_raw(core::ptr::slice_from_raw_parts_mut(
+            ptr,
+            len as usize,
+        )));
+    }
+}
+
+/// See [Renderer::set_line_width].
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_set_line_width(renderer: *mut Renderer, width: f32) {
+    (*renderer).set_line_width(width);
+}
+
+/// Uses Bresenham's algorithm to draw a line.
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_draw_line(renderer: *mut Renderer, x0: f32, y0: f32, x1: f32, y1: f32) {
+    (*renderer).draw_line(x0, y0, x1, y1);
+}
+
+/// Generates the pretty background pattern.
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_generate_background(renderer: *mut Renderer) {
+    (*renderer).generate_background();
+}
 
-    demo_mesh.scale(scalar);
+/// Fills the frame buffer with a pretty pattern.
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_fill_buffer(renderer: *mut Renderer) {
+    (*renderer).fill_buffer();
+}
+
+/// Gets a pointer to the frame buffer: [WIDTH] * [HEIGHT] RGBA pixels, row by row. It stays valid until the
+/// renderer is destroyed.
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_get_buffer(renderer: *const Renderer) -> *const Pixel {
+    (*renderer).buffer.pixels().as_ptr()
+}
 
-    demo_mesh.rot(Axis::X, x_angle);
-    demo_mesh.rot(Axis::Y, y_angle);
-    demo_mesh.rot(Axis::Z, z_angle);
+/// Gets the area of the frame buffer that changed since the frame before, see [Renderer::dirty_rect]. Returns
+/// false, leaving `rect` alone, if nothing changed.
+///
+/// # Safety
+/// `renderer` must come from [mn_create] and `rect` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn mn_get_dirty_rect(renderer: *const Renderer, rect: *mut Rect) -> bool {
+    match (*renderer).dirty_rect() {
+        Some(dirty_rect) => {
+            *rect = dirty_rect;
+            true
+        }
+        None => false,
+    }
+}
 
-    fill_buffer();
-    demo_mesh.render();
+/// Renders the demo into the frame buffer.
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_render(
+    renderer: *mut Renderer,
+    scalar: f32,
+    x_angle: f32,
+    y_angle: f32,
+    z_angle: f32,
+) {
+    (*renderer).render(scalar, x_angle, y_angle, z_angle);
 }
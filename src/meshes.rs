@@ -1,10 +1,12 @@
 //! Some sample meshes for demonstration.
 
 #![allow(dead_code)]
+#![allow(clippy::excessive_precision)]
 
 #[cfg(target_arch = "powerpc")]
 use alloc::vec::Vec;
 
+use crate::skinning::{Skeleton, SkinWeights, SkinnedMesh};
 use crate::*;
 
 #[rustfmt::skip]
@@ -17,7 +19,6 @@ static ICOSPHERE_TRIS: [Tri; 20] = [
     Tri { verts: [Vec3 { x: 0.0, y: -1.0, z: 0.0 }, Vec3 { x: 0.7235999703407288, y: -0.4472149908542633, z: 0.5257200002670288 }, Vec3 { x: -0.27638500928878784, y: -0.4472149908542633, z: 0.8506399989128113 }] }, Tri { verts: [Vec3 { x: 0.7235999703407288, y: -0.4472149908542633, z: 0.5257200002670288 }, Vec3 { x: 0.0, y: -1.0, z: 0.0 }, Vec3 { x: 0.7235999703407288, y: -0.4472149908542633, z: -0.5257200002670288 }] }, Tri { verts: [Vec3 { x: 0.0, y: -1.0, z: 0.0 }, Vec3 { x: -0.27638500928878784, y: -0.4472149908542633, z: 0.8506399989128113 }, Vec3 { x: -0.8944249749183655, y: -0.4472149908542633, z: 0.0 }] }, Tri { verts: [Vec3 { x: 0.0, y: -1.0, z: 0.0 }, Vec3 { x: -0.8944249749183655, y: -0.4472149908542633, z: 0.0 }, Vec3 { x: -0.27638500928878784, y: -0.4472149908542633, z: -0.8506399989128113 }] }, Tri { verts: [Vec3 { x: 0.0, y: -1.0, z: 0.0 }, Vec3 { x: -0.27638500928878784, y: -0.4472149908542633, z: -0.8506399989128113 }, Vec3 { x: 0.7235999703407288, y: -0.4472149908542633, z: -0.5257200002670288 }] }, Tri { verts: [Vec3 { x: 0.7235999703407288, y: -0.4472149908542633, z: 0.5257200002670288 }, Vec3 { x: 0.7235999703407288, y: -0.4472149908542633, z: -0.5257200002670288 }, Vec3 { x: 0.8944249749183655, y: 0.4472149908542633, z: 0.0 }] }, Tri { verts: [Vec3 { x: -0.27638500928878784, y: -0.4472149908542633, z: 0.8506399989128113 }, Vec3 { x: 0.7235999703407288, y: -0.4472149908542633, z: 0.5257200002670288 }, Vec3 { x: 0.27638500928878784, y: 0.4472149908542633, z: 0.8506399989128113 }] }, Tri { verts: [Vec3 { x: -0.8944249749183655, y: -0.4472149908542633, z: 0.0 }, Vec3 { x: -0.27638500928878784, y: -0.4472149908542633, z: 0.8506399989128113 }, Vec3 { x: -0.7235999703407288, y: 0.4472149908542633, z: 0.5257200002670288 }] }, Tri { verts: [Vec3 { x: -0.27638500928878784, y: -0.4472149908542633, z: -0.8506399989128113 }, Vec3 { x: -0.8944249749183655, y: -0.4472149908542633, z: 0.0 }, Vec3 { x: -0.7235999703407288, y: 0.4472149908542633, z: -0.5257200002670288 }] }, Tri { verts: [Vec3 { x: 0.7235999703407288, y: -0.4472149908542633, z: -0.5257200002670288 }, Vec3 { x: -0.27638500928878784, y: -0.4472149908542633, z: -0.8506399989128113 }, Vec3 { x: 0.27638500928878784, y: 0.4472149908542633, z: -0.8506399989128113 }] }, Tri { verts: [Vec3 { x: 0.7235999703407288, y: -0.4472149908542633, z: 0.5257200002670288 }, Vec3 { x: 0.8944249749183655, y: 0.4472149908542633, z: 0.0 }, Vec3 { x: 0.27638500928878784, y: 0.4472149908542633, z: 0.8506399989128113 }] }, Tri { verts: [Vec3 { x: -0.27638500928878784, y: -0.4472149908542633, z: 0.8506399989128113 }, Vec3 { x: 0.27638500928878784, y: 0.4472149908542633, z: 0.8506399989128113 }, Vec3 { x: -0.7235999703407288, y: 0.4472149908542633, z: 0.5257200002670288 }] }, Tri { verts: [Vec3 { x: -0.8944249749183655, y: -0.4472149908542633, z: 0.0 }, Vec3 { x: -0.7235999703407288, y: 0.4472149908542633, z: 0.5257200002670288 }, Vec3 { x: -0.7235999703407288, y: 0.4472149908542633, z: -0.5257200002670288 }] }, Tri { verts: [Vec3 { x: -0.27638500928878784, y: -0.4472149908542633, z: -0.8506399989128113 }, Vec3 { x: -0.7235999703407288, y: 0.4472149908542633, z: -0.5257200002670288 }, Vec3 { x: 0.27638500928878784, y: 0.4472149908542633, z: -0.8506399989128113 }] }, Tri { verts: [Vec3 { x: 0.7235999703407288, y: -0.4472149908542633, z: -0.5257200002670288 }, Vec3 { x: 0.27638500928878784, y: 0.4472149908542633, z: -0.8506399989128113 }, Vec3 { x: 0.8944249749183655, y: 0.4472149908542633, z: 0.0 }] }, Tri { verts: [Vec3 { x: 0.27638500928878784, y: 0.4472149908542633, z: 0.8506399989128113 }, Vec3 { x: 0.8944249749183655, y: 0.4472149908542633, z: 0.0 }, Vec3 { x: 0.0, y: 1.0, z: 0.0 }] }, Tri { verts: [Vec3 { x: -0.7235999703407288, y: 0.4472149908542633, z: 0.5257200002670288 }, Vec3 { x: 0.27638500928878784, y: 0.4472149908542633, z: 0.8506399989128113 }, Vec3 { x: 0.0, y: 1.0, z: 0.0 }] }, Tri { verts: [Vec3 { x: -0.7235999703407288, y: 0.4472149908542633, z: -0.5257200002670288 }, Vec3 { x: -0.7235999703407288, y: 0.4472149908542633, z: 0.5257200002670288 }, Vec3 { x: 0.0, y: 1.0, z: 0.0 }] }, Tri { verts: [Vec3 { x: 0.27638500928878784, y: 0.4472149908542633, z: -0.8506399989128113 }, Vec3 { x: -0.7235999703407288, y: 0.4472149908542633, z: -0.5257200002670288 }, Vec3 { x: 0.0, y: 1.0, z: 0.0 }] }, Tri { verts: [Vec3 { x: 0.8944249749183655, y: 0.4472149908542633, z: 0.0 }, Vec3 { x: 0.27638500928878784, y: 0.4472149908542633, z: -0.8506399989128113 }, Vec3 { x: 0.0, y: 1.0, z: 0.0 }] }
 ];
 
-
 pub fn monkey() -> Mesh {
     Mesh::from(Vec::from(MONKEY_TRIS))
 }
@@ -25,3 +26,71 @@ pub fn monkey() -> Mesh {
 pub fn icosphere() -> Mesh {
     Mesh::from(Vec::from(ICOSPHERE_TRIS))
 }
+
+/// A tapered square tube with a chain of four joints running up its length, for showing off skinning.
+pub fn tentacle() -> (SkinnedMesh, Skeleton) {
+    const LENGTH: f32 = 300.0;
+    const JOINTS: u32 = 4;
+    const RINGS: usize = 12;
+    const SEGMENT: f32 = LENGTH / JOINTS as f32;
+
+    let mut skeleton = Skeleton::new();
+    let mut parent = None;
+    for idx_joint in 0..JOINTS {
+        parent = skeleton.add_joint(
+            parent,
+            Vec3::from([0.0, LENGTH / 2.0 - idx_joint as f32 * SEGMENT, 0.0]),
+        );
+    }
+
+    // corners of a ring, going around the tube, and how far up the tube the ring is
+    let ring = |idx_ring: usize| -> ([Vertex; 4], SkinWeights) {
+        let dist = LENGTH * idx_ring as f32 / RINGS as f32;
+        let half_width = 25.0 * (1.0 - 0.6 * dist / LENGTH);
+        let y = LENGTH / 2.0 - dist;
+        let joint = (dist / SEGMENT) as u32;
+        let weights = if joint + 1 >= JOINTS {
+            SkinWeights::single(JOINTS - 1)
+        } else {
+            SkinWeights::blend(joint, joint + 1, dist / SEGMENT - joint as f32)
+        };
+        let corners = [
+            Vec3::from([-half_width, y, -half_width]),
+            Vec3::from([half_width, y, -half_width]),
+            Vec3::from([half_width, y, half_width]),
+            Vec3::from([-half_width, y, half_width]),
+        ];
+        (corners, weights)
+    };
+
+    let mut tris = Vec::new();
+    let mut weights = Vec::new();
+    for idx_ring in 0..RINGS {
+        let (lower, lower_weights) = ring(idx_ring);
+        let (upper, upper_weights) = ring(idx_ring + 1);
+        for side in 0..4 {
+            let next = (side + 1) % 4;
+            tris.push(Tri {
+                verts: [lower[side], upper[side], upper[next]],
+            });
+            weights.push([lower_weights, upper_weights, upper_weights]);
+            tris.push(Tri {
+                verts: [lower[side], upper[next], lower[next]],
+            });
+            weights.push([lower_weights, upper_weights, lower_weights]);
+        }
+    }
+    for (cap, cap_weights) in [ring(0), ring(RINGS)] {
+        tris.push(Tri {
+            verts: [cap[0], cap[1], cap[2]],
+        });
+        tris.push(Tri {
+            verts: [cap[0], cap[2], cap[3]],
+        });
+        weights.extend([[cap_weights; 3]; 2]);
+    }
+
+    let skinned_mesh =
+        SkinnedMesh::new(Mesh::from(tris), weights).expect("one set of weights per triangle");
+    (skinned_mesh, skeleton)
+}
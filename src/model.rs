@@ -0,0 +1,245 @@
+//! Loading meshes from model files: Wavefront OBJ, and STL in both its ASCII and binary forms. Only the shape is
+//! read; normals, texture coordinates and materials are skipped, and faces with more than three sides are split
+//! into fans of triangles.
+//!
+//! Models come in whatever units and way up they were made in, so they usually need [Mesh::fit] before they're
+//! drawn.
+//!
+//! ```
+//! use meenle_noonle::model;
+//!
+//! let square = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n";
+//! let mut mesh = model::load("square.obj", square.as_bytes()).unwrap();
+//! assert_eq!(mesh.tris.len(), 2);
+//! mesh.fit(100.0);
+//! let (min, max) = mesh.bounds().unwrap();
+//! assert_eq!((min.x, max.x), (-50.0, 50.0));
+//! assert!(model::load("square.obj", b"f 1 2 3").is_err());
+//! ```
+
+use crate::*;
+use core::fmt;
+
+/// Why a model file couldn't be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelError {
+    /// The file name doesn't end in an extension [load] knows.
+    UnknownFormat,
+    /// A line of an OBJ or ASCII STL file doesn't make sense. Lines are counted from 1.
+    Syntax { line: usize, message: &'static str },
+    /// A binary STL file is shorter than its triangle count says it is.
+    Truncated,
+    /// The file has no triangles in it.
+    Empty,
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModelError::UnknownFormat => write!(f, "not an .obj or .stl file"),
+            ModelError::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            ModelError::Truncated => write!(f, "file is cut short"),
+            ModelError::Empty => write!(f, "no triangles"),
+        }
+    }
+}
+
+impl core::error::Error for ModelError {}
+
+/// Loads a model, in the format its file name's extension says: `.obj` or `.stl`, in any case.
+pub fn load(name: &str, bytes: &[u8]) -> Result<Mesh, ModelError> {
+    let extension = name.rsplit_once('.').map_or("", |(_, extension)| extension);
+    if extension.eq_ignore_ascii_case("obj") {
+        parse_obj(bytes)
+    } else if extension.eq_ignore_ascii_case("stl") {
+        parse_stl(bytes)
+    } else {
+        Err(ModelError::UnknownFormat)
+    }
+}
+
+/// Reads a Wavefront OBJ file's vertices (`v`) and faces (`f`). Everything else is skipped.
+pub fn parse_obj(bytes: &[u8]) -> Result<Mesh, ModelError> {
+    let text = text(bytes)?;
+    let mut verts: Vec<Vertex> = Vec::new();
+    let mut tris = Vec::new();
+    for (idx_line, line) in text.lines().enumerate() {
+        let syntax = |message| ModelError::Syntax {
+            line: idx_line + 1,
+            message,
+        };
+        let line = line.split('#').next().unwrap_or("");
+        let mut words = line.split_ascii_whitespace();
+        match words.next() {
+            Some("v") => {
+                verts.push(coords(&mut words).ok_or(syntax("expected three coordinates"))?)
+            }
+            Some("f") => {
+                let mut face = Vec::new();
+                // each corner is `v`, `v/vt`, `v//vn` or `v/vt/vn`, and only `v` matters here
+                for corner in words {
+                    let index: i64 = corner
+                        .split('/')
+                        .next()
+                        .and_then(|index| index.parse().ok())
+                        .ok_or(syntax("expected a vertex number"))?;
+                    // counted from 1, or back from the latest vertex when negative
+                    let index = match index {
+                        1.. => index - 1,
+                        ..=-1 => verts.len() as i64 + index,
+                        0 => return Err(syntax("vertex numbers start at 1")),
+                    };
+                    let vert = usize::try_from(index)
+                        .ok()
+                        .and_then(|index| verts.get(index))
+                        .ok_or(syntax("no such vertex"))?;
+                    face.push(*vert);
+                }
+                if face.len() < 3 {
+                    return Err(syntax("faces need at least three vertices"));
+                }
+                fan(&face, &mut tris);
+            }
+            _ => (),
+        }
+    }
+    mesh(tris)
+}
+
+/// Reads an STL file, binary or ASCII.
+pub fn parse_stl(bytes: &[u8]) -> Result<Mesh, ModelError> {
+    // binary files have an 80 byte header, which can start with `solid` like ASCII ones do, so go by the size,
+    // and whether it's text at all for ones that are cut short
+    let binary_len = bytes
+        .get(80..84)
+        .map(|count| u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize)
+        .and_then(|count| count.checked_mul(50)?.checked_add(84));
+    let ascii = bytes.starts_with(b"solid") && core::str::from_utf8(bytes).is_ok();
+    if binary_len == Some(bytes.len()) || !ascii {
+        parse_binary_stl(bytes)
+    } else {
+        parse_ascii_stl(bytes)
+    }
+}
+
+/// Binary STL: a header, the number of triangles, then 50 bytes for each of them.
+fn parse_binary_stl(bytes: &[u8]) -> Result<Mesh, ModelError> {
+    let count = bytes.get(80..84).ok_or(ModelError::Truncated)?;
+    let count = u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize;
+    let records = bytes[84..].chunks_exact(50);
+    if records.len() < count {
+        return Err(ModelError::Truncated);
+    }
+    let float = |bytes: &[u8]| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    let tris = records
+        .take(count)
+        .map(|record| {
+            // the normal comes first, and two bytes of attributes last
+            let vert = |idx: usize| {
+                let at = 12 + idx * 12;
+                Vec3::from([
+                    float(&record[at..]),
+                    float(&record[at + 4..]),
+                    float(&record[at + 8..]),
+                ])
+            };
+            Tri {
+                verts: [vert(0), vert(1), vert(2)],
+            }
+        })
+        .collect();
+    mesh(tris)
+}
+
+/// ASCII STL: `facet`s, each with an `outer loop` of `vertex x y z` lines.
+fn parse_ascii_stl(bytes: &[u8]) -> Result<Mesh, ModelError> {
+    let text = text(bytes)?;
+    let mut face = Vec::new();
+    let mut tris = Vec::new();
+    for (idx_line, line) in text.lines().enumerate() {
+        let syntax = |message| ModelError::Syntax {
+            line: idx_line + 1,
+            message,
+        };
+        let mut words = line.split_ascii_whitespace();
+        match words.next() {
+            Some("vertex") => {
+                face.push(coords(&mut words).ok_or(syntax("expected three coordinates"))?)
+            }
+            Some("endloop") => {
+                if face.len() < 3 {
+                    return Err(syntax("facets need at least three vertices"));
+                }
+                fan(&face, &mut tris);
+                face.clear();
+            }
+            _ => (),
+        }
+    }
+    mesh(tris)
+}
+
+/// The file as text, for the text formats.
+fn text(bytes: &[u8]) -> Result<&str, ModelError> {
+    core::str::from_utf8(bytes).map_err(|err| ModelError::Syntax {
+        line: 1 + bytes[..err.valid_up_to()]
+            .iter()
+            .filter(|&&byte| byte == b'\n')
+            .count(),
+        message: "not UTF-8 text",
+    })
+}
+
+/// Reads the next three words as a point.
+fn coords<'a>(words: &mut impl Iterator<Item = &'a str>) -> Option<Vec3> {
+    let mut coord = || words.next()?.parse().ok();
+    Some(Vec3::from([coord()?, coord()?, coord()?]))
+}
+
+/// Splits a polygon into triangles that all share its first vertex.
+fn fan(face: &[Vertex], tris: &mut Vec<Tri>) {
+    for pair in face[1..].windows(2) {
+        tris.push(Tri {
+            verts: [face[0], pair[0], pair[1]],
+        });
+    }
+}
+
+fn mesh(tris: Vec<Tri>) -> Result<Mesh, ModelError> {
+    if tris.is_empty() {
+        Err(ModelError::Empty)
+    } else {
+        Ok(Mesh::from(tris))
+    }
+}
+
+impl Mesh {
+    /// The smallest and largest corners of the box around every vertex. [None] without any triangles.
+    pub fn bounds(&self) -> Option<(Vec3, Vec3)> {
+        let first = self.tris.first()?.verts[0];
+        Some(
+            self.tris
+                .iter()
+                .flat_map(|tri| tri.verts)
+                .fold((first, first), |(min, max), vert| {
+                    (
+                        Vec3::from([min.x.min(vert.x), min.y.min(vert.y), min.z.min(vert.z)]),
+                        Vec3::from([max.x.max(vert.x), max.y.max(vert.y), max.z.max(vert.z)]),
+                    )
+                }),
+        )
+    }
+
+    /// Centres the mesh on the origin and scales it so the longest side of its [Mesh::bounds] is `size` long.
+    pub fn fit(&mut self, size: f32) {
+        let Some((min, max)) = self.bounds() else {
+            return;
+        };
+        let centre = 0.5 * (min + max);
+        self.translate(-1.0 * centre);
+        let longest = (max.x - min.x).max(max.y - min.y).max(max.z - min.z);
+        if longest > f32::EPSILON {
+            self.scale(size / longest);
+        }
+    }
+}
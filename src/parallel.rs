@@ -0,0 +1,198 @@
+//! Multithreaded rasterization, behind the `parallel` feature. The frame buffer is split into horizontal bands,
+//! one per thread, and every thread draws the whole mesh clipped to its own band. Each pixel is still written by
+//! the same lines, in the same order, as with the serial path, so the output is bit-identical.
+//!
+//! The bands can't share the frame buffer's [FrameBuffer::drawn] area, so it's worked out from the lines' bounding
+//! boxes instead. It always covers the serial path's, but can be up to a few pixels bigger on each side, more so
+//! around thick lines, which only means [Renderer::dirty_rect] copies a few pixels more than it needs to.
+
+use crate::*;
+
+/// One thread's rows of the frame buffer, drawn into like the whole of it.
+struct Band<'a, F: PixelFormat> {
+    /// The band's rows, packed like the frame buffer's.
+    bytes: &'a mut [u8],
+    format: PhantomData<F>,
+    /// Size of the whole frame buffer.
+    size: (usize, usize),
+    /// Rows of the frame buffer the band covers.
+    rows: core::ops::Range<i32>,
+}
+
+impl<F: PixelFormat> RenderTarget for Band<'_, F> {
+    fn size(&self) -> (usize, usize) {
+        self.size
+    }
+
+    fn plot_pixel(&mut self, x: i32, y: i32, pixel: &Pixel) {
+        if self.rows.contains(&y) && (0..self.size.0 as i32).contains(&x) {
+            let stride = F::stride(self.size.0);
+            let row = (y - self.rows.start) as usize * stride;
+            F::encode(pixel, &mut self.bytes[row..row + stride], x as usize);
+        }
+    }
+
+    fn draw_line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, pixel: &Pixel) {
+        let [x0, y0, x1, y1] = centered(self.size, [x0, y0, x1, y1]);
+        // most lines miss most bands
+        if y0.max(y1) < self.rows.start || y0.min(y1) >= self.rows.end {
+            return;
+        }
+        bresenham(x0, y0, x1, y1, |x, y| self.plot_pixel(x, y, pixel));
+    }
+
+    fn draw_thick_line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, width: f32, pixel: &Pixel) {
+        if width <= 1.0 {
+            self.draw_line(x0, y0, x1, y1, pixel);
+            return;
+        }
+        let [_, top, _, bottom] = centered(self.size, [0.0, y0.min(y1), 0.0, y0.max(y1)]);
+        let reach = width as i32 / 2 + 1;
+        if bottom + reach < self.rows.start || top - reach >= self.rows.end {
+            return;
+        }
+        primitives::centred_thick_line(self, [x0, y0, x1, y1], width, pixel);
+    }
+
+    fn fill_span(&mut self, x: i32, y: i32, len: usize, pixel: &Pixel) {
+        let (start, end) = (x.max(0), (x + len as i32).min(self.size.0 as i32));
+        if !self.rows.contains(&y) || start >= end {
+            return;
+        }
+        let stride = F::stride(self.size.0);
+        let row = &mut self.bytes[(y - self.rows.start) as usize * stride..][..stride];
+        for x in start..end {
+            F::encode(pixel, row, x as usize);
+        }
+    }
+}
+
+/// Draws the mesh into the frame buffer with edges `line_width` pixels wide, splitting the work between as many
+/// threads as the machine has cores.
+pub(crate) fn render_mesh<F: PixelFormat>(
+    mesh: &Mesh,
+    frame_buffer: &mut FrameBuffer<F>,
+    line_width: f32,
+) {
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    render_mesh_in_bands(mesh, frame_buffer, line_width, threads);
+}
+
+/// Draws the mesh like [render_mesh], in `bands` bands on a thread each. One band, or fewer rows than bands, draws
+/// on the calling thread like the serial path.
+fn render_mesh_in_bands<F: PixelFormat>(
+    mesh: &Mesh,
+    frame_buffer: &mut FrameBuffer<F>,
+    line_width: f32,
+    bands: usize,
+) {
+    let threads = bands.min(frame_buffer.height);
+    if threads <= 1 || frame_buffer.width == 0 {
+        mesh.render_with_width(frame_buffer, line_width);
+        return;
+    }
+
+    let size = (frame_buffer.width, frame_buffer.height);
+    let width = frame_buffer.width;
+    let stride = frame_buffer.stride();
+    let band_height = frame_buffer.height.div_ceil(threads);
+
+    // the bands can't share the frame buffer's drawn area, so work it out from the lines' bounding boxes, grown
+    // by how far thick lines reach past their ends
+    let reach = if line_width <= 1.0 {
+        0
+    } else {
+        line_width as i32 / 2 + 1
+    };
+    let lines = mesh.tris.iter().flat_map(|tri| tri.edges());
+    for [x0, y0, x1, y1] in lines.map(|line| centered(size, line)) {
+        let (left, right) = (
+            (x0.min(x1) - reach).max(0),
+            (x0.max(x1) + reach).min(width as i32 - 1),
+        );
+        let (top, bottom) = (
+            (y0.min(y1) - reach).max(0),
+            (y0.max(y1) + reach).min(frame_buffer.height as i32 - 1),
+        );
+        if left <= right && top <= bottom {
+            frame_buffer.mark_drawn(Rect {
+                x: left as u32,
+                y: top as u32,
+                width: (right - left + 1) as u32,
+                height: (bottom - top + 1) as u32,
+            });
+        }
+    }
+
+    std::thread::scope(|scope| {
+        for (idx_band, bytes) in frame_buffer
+            .bytes
+            .chunks_mut(band_height * stride)
+            .enumerate()
+        {
+            scope.spawn(move || {
+                let first_row = (idx_band * band_height) as i32;
+                let mut band = Band::<F> {
+                    rows: first_row..first_row + (bytes.len() / stride) as i32,
+                    bytes,
+                    format: PhantomData,
+                    size,
+                };
+                mesh.render_with_width(&mut band, line_width);
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::{Mono1, Rgb565};
+
+    /// Every demo mesh, turned so its edges run every which way.
+    fn meshes() -> Vec<Mesh> {
+        let (tentacle, skeleton) = meshes::tentacle();
+        let mut meshes: Vec<Mesh> = (0..demo::MESH_COUNT).filter_map(demo::mesh).collect();
+        meshes.push(tentacle.pose(&skeleton));
+        for mesh in &mut meshes {
+            mesh.rot(Axis::Y, 0.7);
+            mesh.rot(Axis::X, 0.3);
+        }
+        meshes
+    }
+
+    fn check_bands<F: PixelFormat>() {
+        for mesh in meshes() {
+            for line_width in [1.0, 2.0, 3.5, 15.0] {
+                let mut serial = FrameBuffer::<F>::with_format(101, 77);
+                mesh.render_with_width(&mut serial, line_width);
+                let serial_drawn = serial.drawn().unwrap();
+                for bands in 2..=4 {
+                    let mut banded = FrameBuffer::<F>::with_format(101, 77);
+                    render_mesh_in_bands(&mesh, &mut banded, line_width, bands);
+                    assert!(
+                        banded.bytes() == serial.bytes(),
+                        "{line_width} px lines in {bands} bands"
+                    );
+                    let drawn = banded.drawn().unwrap();
+                    assert_eq!(drawn.union(serial_drawn), drawn);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bands_match_serial_rgba() {
+        check_bands::<Rgba8888>();
+    }
+
+    #[test]
+    fn bands_match_serial_rgb565() {
+        check_bands::<Rgb565>();
+    }
+
+    #[test]
+    fn bands_match_serial_mono() {
+        check_bands::<Mono1>();
+    }
+}
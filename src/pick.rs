@@ -0,0 +1,207 @@
+//! Picking: finding what's under a pixel, for interactive viewers. Projection is straight on, so the ray through a
+//! pixel runs along `z`, and the triangle it hits first is the one with the smallest `z` there. In wireframe it's
+//! usually the nearest edge that matters instead, which [Mesh::nearest_edge] finds. Positions are relative to the
+//! centre of the frame, like lines; [Renderer::pick] and [Renderer::pick_edge] take pixels and look at the demo
+//! mesh as it was last drawn.
+//!
+//! ```
+//! use meenle_noonle::{Mesh, Vec3};
+//!
+//! let cube = Mesh::cube(Vec3::from([-50.0; 3]), Vec3::from([50.0; 3]));
+//! // straight through the middle, the front face is hit first
+//! let hit = cube.pick(0.0, 0.0).unwrap();
+//! assert_eq!(hit.point.z, -50.0);
+//! assert!(cube.pick(60.0, 0.0).is_none());
+//! // a pixel in from the right side
+//! let edge = cube.nearest_edge(49.0, 0.0).unwrap();
+//! assert_eq!(edge.distance, 1.0);
+//! ```
+
+use crate::*;
+
+/// Where a ray hit a mesh.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Hit {
+    /// Index of the triangle in [Mesh::tris].
+    pub tri: u32,
+    /// Weights of the triangle's three vertices at the hit point, adding up to 1.
+    pub barycentric: [f32; 3],
+    /// The hit point, in the mesh's space.
+    pub point: Vec3,
+}
+
+/// The edge nearest a point on the screen.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct EdgeHit {
+    /// Index of the triangle in [Mesh::tris].
+    pub tri: u32,
+    /// Which of the triangle's edges: edge `n` runs from vertex `n` to the next one round.
+    pub edge: u32,
+    /// How far along the edge the nearest point is, from 0 at its start to 1 at its end.
+    pub t: f32,
+    /// Distance from the point to the edge on the screen, in pixels.
+    pub distance: f32,
+}
+
+impl Mesh {
+    /// Casts a ray through `(x, y)` and finds the nearest triangle it hits. Triangles seen edge-on are missed.
+    pub fn pick(&self, x: f32, y: f32) -> Option<Hit> {
+        let mut nearest: Option<Hit> = None;
+        for (idx_tri, tri) in self.tris.iter().enumerate() {
+            let [v0, v1, v2] = tri.verts;
+            let area = (v1.y - v2.y) * (v0.x - v2.x) + (v2.x - v1.x) * (v0.y - v2.y);
+            if area.abs() < f32::EPSILON {
+                continue;
+            }
+            let b0 = ((v1.y - v2.y) * (x - v2.x) + (v2.x - v1.x) * (y - v2.y)) / area;
+            let b1 = ((v2.y - v0.y) * (x - v2.x) + (v0.x - v2.x) * (y - v2.y)) / area;
+            let b2 = 1.0 - b0 - b1;
+            if b0 < 0.0 || b1 < 0.0 || b2 < 0.0 {
+                continue;
+            }
+            let point = b0 * v0 + b1 * v1 + b2 * v2;
+            if nearest.is_none_or(|hit| point.z < hit.point.z) {
+                nearest = Some(Hit {
+                    tri: idx_tri as u32,
+                    barycentric: [b0, b1, b2],
+                    point,
+                });
+            }
+        }
+        nearest
+    }
+
+    /// Finds the edge that passes nearest `(x, y)` on the screen, however far away. Where edges are as near as
+    /// each other, like the two copies of an edge triangles share, the one in front wins.
+    pub fn nearest_edge(&self, x: f32, y: f32) -> Option<EdgeHit> {
+        // the best so far, with its distance squared and its depth at the nearest point
+        let mut nearest: Option<(EdgeHit, f32, f32)> = None;
+        for (idx_tri, tri) in self.tris.iter().enumerate() {
+            for idx_edge in 0..3 {
+                let (from, to) = (tri.verts[idx_edge], tri.verts[(idx_edge + 1) % 3]);
+                let (along_x, along_y) = (to.x - from.x, to.y - from.y);
+                let len_squared = along_x * along_x + along_y * along_y;
+                let t = if len_squared < f32::EPSILON {
+                    0.0
+                } else {
+                    (((x - from.x) * along_x + (y - from.y) * along_y) / len_squared)
+                        .clamp(0.0, 1.0)
+                };
+                let (dx, dy) = (from.x + along_x * t - x, from.y + along_y * t - y);
+                let distance_squared = dx * dx + dy * dy;
+                let z = from.z + (to.z - from.z) * t;
+                let nearer = nearest.is_none_or(|(_, best, best_z)| {
+                    distance_squared < best - 0.01
+                        || (distance_squared <= best + 0.01 && z < best_z)
+                });
+                if nearer {
+                    let edge = EdgeHit {
+                        tri: idx_tri as u32,
+                        edge: idx_edge as u32,
+                        t,
+                        distance: 0.0,
+                    };
+                    nearest = Some((edge, distance_squared, z));
+                }
+            }
+        }
+        nearest.map(|(edge, distance_squared, _)| EdgeHit {
+            distance: distance_squared.sqrt(),
+            ..edge
+        })
+    }
+}
+
+impl<F: PixelFormat> Renderer<F> {
+    /// The demo mesh as it's on the screen, turned by the last [Renderer::render_spin] or [Renderer::render_view].
+    fn shown_mesh(&self) -> Option<&Mesh> {
+        self.spun_mesh.as_ref().or(self.demo_mesh.as_ref())
+    }
+
+    /// Converts a pixel to a position relative to the centre of the frame, the way meshes are drawn.
+    fn relative_to_centre(&self, x: i32, y: i32) -> (f32, f32) {
+        (
+            (x - (self.buffer.width / 2) as i32) as f32,
+            (y - (self.buffer.height / 2) as i32) as f32,
+        )
+    }
+
+    /// The demo triangle under pixel `(x, y)`, as it was last drawn. Stereo views aren't taken into account.
+    /// See [Mesh::pick].
+    pub fn pick(&self, x: i32, y: i32) -> Option<Hit> {
+        let (x, y) = self.relative_to_centre(x, y);
+        self.shown_mesh()?.pick(x, y)
+    }
+
+    /// The demo mesh's edge nearest pixel `(x, y)`, as it was last drawn. See [Mesh::nearest_edge].
+    pub fn pick_edge(&self, x: i32, y: i32) -> Option<EdgeHit> {
+        let (x, y) = self.relative_to_centre(x, y);
+        self.shown_mesh()?.nearest_edge(x, y)
+    }
+
+    /// Draws over the edges of one of the demo mesh's triangles, as it was last drawn, a little wider than the
+    /// mesh's own, to pick it out. Draw it after rendering the frame. Indices past the end are ignored.
+    pub fn highlight_triangle(&mut self, tri: usize, pixel: Pixel) {
+        let Some(tri) = self
+            .shown_mesh()
+            .and_then(|mesh| mesh.tris.get(tri))
+            .copied()
+        else {
+            return;
+        };
+        let width = self.line_width + 2.0;
+        for [x0, y0, x1, y1] in tri.edges() {
+            self.buffer.draw_thick_line(x0, y0, x1, y1, width, &pixel);
+        }
+    }
+}
+
+/// Picks the demo triangle under pixel `(x, y)`, see [Renderer::pick]. Returns false, leaving `hit` alone, if
+/// there isn't one.
+///
+/// # Safety
+/// `renderer` must come from [mn_create] and `hit` must be valid for writes. It needn't be aligned, so it can come
+/// from [mn_alloc].
+#[no_mangle]
+pub unsafe extern "C" fn mn_pick(renderer: *const Renderer, x: i32, y: i32, hit: *mut Hit) -> bool {
+    match (*renderer).pick(x, y) {
+        Some(found) => {
+            hit.write_unaligned(found);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Finds the demo mesh's edge nearest pixel `(x, y)`, see [Renderer::pick_edge]. Returns false, leaving `hit`
+/// alone, if there's no mesh.
+///
+/// # Safety
+/// `renderer` must come from [mn_create] and `hit` must be valid for writes. It needn't be aligned, so it can come
+/// from [mn_alloc].
+#[no_mangle]
+pub unsafe extern "C" fn mn_pick_edge(
+    renderer: *const Renderer,
+    x: i32,
+    y: i32,
+    hit: *mut EdgeHit,
+) -> bool {
+    match (*renderer).pick_edge(x, y) {
+        Some(found) => {
+            hit.write_unaligned(found);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Picks out one of the demo mesh's triangles in a `0xRRGGBB` colour, see [Renderer::highlight_triangle].
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_highlight_triangle(renderer: *mut Renderer, tri: u32, color: u32) {
+    (*renderer).highlight_triangle(tri as usize, Pixel::from_hex(color));
+}
@@ -0,0 +1,226 @@
+//! PNG and animated PNG encoding of frame buffers, compressed with the built-in [crate::deflate].
+
+use crate::deflate;
+use crate::*;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// CRC-32 lookup table, worked out at compile time.
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut idx = 0;
+    while idx < 256 {
+        let mut crc = idx as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                0xedb88320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[idx] = crc;
+        idx += 1;
+    }
+    table
+};
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, &byte| {
+        CRC_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Appends a chunk: its length, type, data and a CRC of the type and data.
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend((data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend(kind);
+    out.extend(data);
+    let crc = crc32(&out[start..]);
+    out.extend(crc.to_be_bytes());
+}
+
+/// Predicts a byte from the ones to its left, above, and above-left, as the Paeth filter does.
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let estimate = left as i16 + up as i16 - up_left as i16;
+    let (to_left, to_up, to_up_left) = (
+        (estimate - left as i16).abs(),
+        (estimate - up as i16).abs(),
+        (estimate - up_left as i16).abs(),
+    );
+    if to_left <= to_up && to_left <= to_up_left {
+        left
+    } else if to_up <= to_up_left {
+        up
+    } else {
+        up_left
+    }
+}
+
+/// Filters every row of the image, each with whichever filter makes it smallest, which is what deflate
+/// compresses. `bytes_per_pixel` is how far back the "left" byte is.
+fn filter_rows(data: &[u8], stride: usize, bytes_per_pixel: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / stride.max(1));
+    let zeros = vec![0; stride];
+    let mut candidate = vec![0; stride];
+    let mut best = vec![0; stride];
+    for (idx_row, row) in data.chunks_exact(stride.max(1)).enumerate() {
+        let above = if idx_row == 0 {
+            &zeros[..]
+        } else {
+            &data[(idx_row - 1) * stride..idx_row * stride]
+        };
+        let mut best_filter = 0;
+        let mut best_cost = u64::MAX;
+        for filter in 0..5u8 {
+            for idx in 0..stride {
+                let left = if idx >= bytes_per_pixel {
+                    row[idx - bytes_per_pixel]
+                } else {
+                    0
+                };
+                let up_left = if idx >= bytes_per_pixel {
+                    above[idx - bytes_per_pixel]
+                } else {
+                    0
+                };
+                let prediction = match filter {
+                    0 => 0,
+                    1 => left,
+                    2 => above[idx],
+                    3 => ((left as u16 + above[idx] as u16) / 2) as u8,
+                    _ => paeth(left, above[idx], up_left),
+                };
+                candidate[idx] = row[idx].wrapping_sub(prediction);
+            }
+            // the usual heuristic: smallest sum of the bytes taken as signed
+            let cost = candidate
+                .iter()
+                .map(|&byte| (byte as i8).unsigned_abs() as u64)
+                .sum();
+            if cost < best_cost {
+                (best_filter, best_cost) = (filter, cost);
+                best.copy_from_slice(&candidate);
+            }
+        }
+        out.push(best_filter);
+        out.extend(&best);
+    }
+    out
+}
+
+/// The IHDR chunk's data for an 8-bit RGBA image.
+fn header(width: usize, height: usize) -> Vec<u8> {
+    let mut header = Vec::with_capacity(13);
+    header.extend((width as u32).to_be_bytes());
+    header.extend((height as u32).to_be_bytes());
+    // 8 bits per channel, RGBA, deflate, adaptive filtering, not interlaced
+    header.extend([8, 6, 0, 0, 0]);
+    header
+}
+
+/// Filters and compresses the frame buffer's pixels, ready to go in an IDAT chunk.
+fn compress_pixels(frame_buffer: &FrameBuffer) -> Vec<u8> {
+    let bytes: Vec<u8> = frame_buffer
+        .pixels()
+        .iter()
+        .flat_map(|pxl| [pxl.r, pxl.g, pxl.b, pxl.a])
+        .collect();
+    deflate::zlib(&filter_rows(&bytes, frame_buffer.width * 4, 4))
+}
+
+/// Encodes the frame buffer as an 8-bit RGBA PNG file.
+pub fn encode(frame_buffer: &FrameBuffer) -> Vec<u8> {
+    let mut out = SIGNATURE.to_vec();
+    write_chunk(
+        &mut out,
+        b"IHDR",
+        &header(frame_buffer.width, frame_buffer.height),
+    );
+    write_chunk(&mut out, b"IDAT", &compress_pixels(frame_buffer));
+    write_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+/// Builds a looping animated PNG one frame at a time. Like the GIF encoder, only the area that changed since the
+/// frame before is stored. Viewers without APNG support show the first frame.
+pub struct ApngEncoder {
+    width: usize,
+    height: usize,
+    /// Frame delay, in milliseconds.
+    delay: u16,
+    /// Every chunk after the animation control chunk, which can't be written until the frame count is known.
+    chunks: Vec<u8>,
+    frames: u32,
+    /// Sequence number of the next frame control or frame data chunk.
+    sequence: u32,
+    /// The frame before, to work out what changed.
+    previous: Option<FrameBuffer>,
+}
+
+impl ApngEncoder {
+    /// Starts a `width` by `height` APNG playing at `fps` frames per second.
+    pub fn new(width: usize, height: usize, fps: f32) -> ApngEncoder {
+        ApngEncoder {
+            width,
+            height,
+            delay: (1000.0 / fps + 0.5).clamp(1.0, u16::MAX as f32) as u16,
+            chunks: Vec::new(),
+            frames: 0,
+            sequence: 0,
+            previous: None,
+        }
+    }
+
+    /// Adds a frame, which must be the size the APNG was started with.
+    pub fn add_frame(&mut self, frame_buffer: &FrameBuffer) {
+        let full = Rect::full(self.width, self.height);
+        // the first frame is the default image, so it has to be whole. Unchanged frames still need a pixel
+        let rect = match &self.previous {
+            Some(previous) => frame_buffer.changed_area(previous).unwrap_or(Rect {
+                width: 1,
+                height: 1,
+                ..full
+            }),
+            None => full,
+        };
+
+        let mut control = self.sequence.to_be_bytes().to_vec();
+        for num in [rect.width, rect.height, rect.x, rect.y] {
+            control.extend(num.to_be_bytes());
+        }
+        control.extend(self.delay.to_be_bytes());
+        control.extend(1000u16.to_be_bytes());
+        // leave the frame in place afterwards, and replace rather than blend with what was there
+        control.extend([0, 0]);
+        write_chunk(&mut self.chunks, b"fcTL", &control);
+        self.sequence += 1;
+
+        let pixels = compress_pixels(&frame_buffer.crop(rect));
+        if self.previous.is_none() {
+            write_chunk(&mut self.chunks, b"IDAT", &pixels);
+        } else {
+            let mut data = self.sequence.to_be_bytes().to_vec();
+            data.extend(pixels);
+            write_chunk(&mut self.chunks, b"fdAT", &data);
+            self.sequence += 1;
+        }
+        self.frames += 1;
+        self.previous = Some(frame_buffer.clone());
+    }
+
+    /// Ends the APNG and returns the file.
+    pub fn finish(mut self) -> Vec<u8> {
+        let mut out = SIGNATURE.to_vec();
+        write_chunk(&mut out, b"IHDR", &header(self.width, self.height));
+        let mut animation = self.frames.to_be_bytes().to_vec();
+        // loop forever
+        animation.extend(0u32.to_be_bytes());
+        write_chunk(&mut out, b"acTL", &animation);
+        out.append(&mut self.chunks);
+        write_chunk(&mut out, b"IEND", &[]);
+        out
+    }
+}
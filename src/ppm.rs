@@ -0,0 +1,35 @@
+//! Netpbm encoding of frame buffers: binary PPM, which has no alpha channel, and PAM, which keeps it. Neither is
+//! compressed, so they're quick to write and easy for other tools to read.
+
+#[cfg(target_arch = "powerpc")]
+use alloc::format;
+
+use crate::*;
+
+/// Encodes the frame buffer as a binary (P6) PPM file. Alpha is dropped.
+pub fn encode_ppm(frame_buffer: &FrameBuffer) -> Vec<u8> {
+    let mut out = format!("P6\n{} {}\n255\n", frame_buffer.width, frame_buffer.height).into_bytes();
+    out.extend(
+        frame_buffer
+            .pixels()
+            .iter()
+            .flat_map(|pxl| [pxl.r, pxl.g, pxl.b]),
+    );
+    out
+}
+
+/// Encodes the frame buffer as an RGBA PAM (P7) file.
+pub fn encode_pam(frame_buffer: &FrameBuffer) -> Vec<u8> {
+    let mut out = format!(
+        "P7\nWIDTH {}\nHEIGHT {}\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n",
+        frame_buffer.width, frame_buffer.height
+    )
+    .into_bytes();
+    out.extend(
+        frame_buffer
+            .pixels()
+            .iter()
+            .flat_map(|pxl| [pxl.r, pxl.g, pxl.b, pxl.a]),
+    );
+    out
+}
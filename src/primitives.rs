@@ -0,0 +1,994 @@
+//! 2D shapes for overlays, gizmos and UIs drawn over the render: rectangles, circles and ellipses, thick lines,
+//! polylines and filled polygons. They draw into any [RenderTarget] through [RenderTarget::plot_pixel] and
+//! [RenderTarget::fill_span].
+//!
+//! Unlike [RenderTarget::draw_line], coordinates are in pixels from the top left corner, like [font::draw_text].
+//! Shapes with `f32` coordinates cover the pixels whose centres they contain, and pixel `(x, y)` has its centre at
+//! `(x + 0.5, y + 0.5)`.
+//!
+//! ```
+//! use meenle_noonle::primitives::{self, LineCap};
+//! use meenle_noonle::{FrameBuffer, Pixel};
+//!
+//! let mut frame_buffer = FrameBuffer::new(64, 64);
+//! primitives::fill_circle(&mut frame_buffer, 32, 32, 10, &Pixel::rgb(255, 0, 0));
+//! let white = Pixel::rgb(255, 255, 255);
+//! primitives::thick_line(&mut frame_buffer, 4.0, 60.0, 60.0, 4.0, 3.0, LineCap::Round, &white);
+//! assert_eq!(frame_buffer.pixel(32, 32), Some(white));
+//! assert_eq!(frame_buffer.pixel(32, 40), Some(Pixel::rgb(255, 0, 0)));
+//! ```
+
+use crate::*;
+
+/// How the ends of thick lines are drawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineCap {
+    /// Cut off square at the end points.
+    #[default]
+    Butt,
+    /// Squared off half the width past the end points.
+    Square,
+    /// Rounded off, half the width past the end points.
+    Round,
+}
+
+impl LineCap {
+    /// Picks a cap by number, for the C exports: 0 is butt, 1 square and 2 round.
+    pub fn from_id(id: u32) -> Option<LineCap> {
+        Some(match id {
+            0 => LineCap::Butt,
+            1 => LineCap::Square,
+            2 => LineCap::Round,
+            _ => return None,
+        })
+    }
+}
+
+/// Which parts of a self-intersecting polygon are inside it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FillRule {
+    /// Inside where a ray out crosses an odd number of edges, so overlapping parts leave holes.
+    #[default]
+    EvenOdd,
+    /// Inside wherever the edges wind around at all, so overlapping parts are filled.
+    NonZero,
+}
+
+impl FillRule {
+    /// Picks a rule by number, for the C exports: 0 is even-odd and 1 non-zero.
+    pub fn from_id(id: u32) -> Option<FillRule> {
+        Some(match id {
+            0 => FillRule::EvenOdd,
+            1 => FillRule::NonZero,
+            _ => return None,
+        })
+    }
+}
+
+/// Outlines the `width` by `height` rectangle with its top left corner at `(x, y)`, one pixel thick.
+pub fn rect<T: RenderTarget + ?Sized>(
+    target: &mut T,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    pixel: &Pixel,
+) {
+    if width == 0 || height == 0 {
+        return;
+    }
+    // in i64, as big rectangles can reach past i32::MAX
+    let (x, y) = (x as i64, y as i64);
+    let (right, bottom) = (x + width as i64 - 1, y + height as i64 - 1);
+    clipped_span(target, x, right, y, pixel);
+    if bottom > y {
+        clipped_span(target, x, right, bottom, pixel);
+    }
+    let (_, target_height) = target.size();
+    for row in (y + 1).max(0)..bottom.min(target_height as i64) {
+        clipped_span(target, x, x, row, pixel);
+        clipped_span(target, right, right, row, pixel);
+    }
+}
+
+/// Fills the `width` by `height` rectangle with its top left corner at `(x, y)`.
+pub fn fill_rect<T: RenderTarget + ?Sized>(
+    target: &mut T,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    pixel: &Pixel,
+) {
+    let (x, y) = (x as i64, y as i64);
+    let (_, target_height) = target.size();
+    // rows off the target would only be skipped one at a time
+    for row in y.max(0)..(y + height as i64).min(target_height as i64) {
+        clipped_span(target, x, x + width as i64 - 1, row, pixel);
+    }
+}
+
+/// Outlines the circle around pixel `(centre_x, centre_y)`, one pixel thick.
+pub fn circle<T: RenderTarget + ?Sized>(
+    target: &mut T,
+    centre_x: i32,
+    centre_y: i32,
+    radius: u32,
+    pixel: &Pixel,
+) {
+    ellipse(target, centre_x, centre_y, radius, radius, pixel);
+}
+
+/// Fills the circle around pixel `(centre_x, centre_y)`.
+pub fn fill_circle<T: RenderTarget + ?Sized>(
+    target: &mut T,
+    centre_x: i32,
+    centre_y: i32,
+    radius: u32,
+    pixel: &Pixel,
+) {
+    fill_ellipse(target, centre_x, centre_y, radius, radius, pixel);
+}
+
+/// Outlines the ellipse around pixel `(centre_x, centre_y)`, `radius_x` pixels either side and `radius_y` above
+/// and below, one pixel thick.
+pub fn ellipse<T: RenderTarget + ?Sized>(
+    target: &mut T,
+    centre_x: i32,
+    centre_y: i32,
+    radius_x: u32,
+    radius_y: u32,
+    pixel: &Pixel,
+) {
+    let (centre_x, centre_y) = (centre_x as i64, centre_y as i64);
+    if radius_x.max(radius_y) > MIDPOINT_MAX_RADIUS {
+        let height = target.size().1;
+        scanline_ellipse(height, centre_y, radius_x, radius_y, |y, inner, outer| {
+            clipped_span(target, centre_x - outer, centre_x - inner, y, pixel);
+            clipped_span(target, centre_x + inner, centre_x + outer, y, pixel);
+        });
+        return;
+    }
+    midpoint_ellipse(radius_x, radius_y, |x, y| {
+        for (x, y) in [(x, y), (-x, y), (x, -y), (-x, -y)] {
+            clipped_span(target, centre_x + x, centre_x + x, centre_y + y, pixel);
+        }
+    });
+}
+
+/// Fills the ellipse around pixel `(centre_x, centre_y)`, `radius_x` pixels either side and `radius_y` above and
+/// below.
+pub fn fill_ellipse<T: RenderTarget + ?Sized>(
+    target: &mut T,
+    centre_x: i32,
+    centre_y: i32,
+    radius_x: u32,
+    radius_y: u32,
+    pixel: &Pixel,
+) {
+    let (centre_x, centre_y) = (centre_x as i64, centre_y as i64);
+    if radius_x.max(radius_y) > MIDPOINT_MAX_RADIUS {
+        let height = target.size().1;
+        scanline_ellipse(height, centre_y, radius_x, radius_y, |y, _, outer| {
+            clipped_span(target, centre_x - outer, centre_x + outer, y, pixel);
+        });
+        return;
+    }
+    // the widest point on each row is the last one visited, so later spans cover earlier ones
+    midpoint_ellipse(radius_x, radius_y, |x, y| {
+        for y in [centre_y - y, centre_y + y] {
+            clipped_span(target, centre_x - x, centre_x + x, y, pixel);
+        }
+    });
+}
+
+/// Radii past this are drawn by [scanline_ellipse]. Up to it, [midpoint_ellipse]'s decision variables, which grow
+/// with the fourth power of the radii, fit in an `i64` with plenty to spare, and it never takes more than a few
+/// tens of thousands of steps.
+const MIDPOINT_MAX_RADIUS: u32 = 1 << 14;
+
+/// Walks the first quadrant of an ellipse's outline with the midpoint algorithm, from the top round to the right,
+/// calling `visit` on every pixel relative to the centre. Radii mustn't be over [MIDPOINT_MAX_RADIUS].
+fn midpoint_ellipse(radius_x: u32, radius_y: u32, mut visit: impl FnMut(i64, i64)) {
+    let (rx, ry) = (radius_x as i64, radius_y as i64);
+    if ry == 0 {
+        for x in 0..=rx {
+            visit(x, 0);
+        }
+        return;
+    }
+    let (rx2, ry2) = (rx * rx, ry * ry);
+    let (mut x, mut y) = (0, ry);
+    // the outline's slope, as the change in the decision variable for a step along x or y
+    let (mut dx, mut dy) = (0, 2 * rx2 * y);
+    // where the outline's slope is under 1, step along x. Decision variables are four times over, to stay whole
+    let mut decision = 4 * ry2 - 4 * rx2 * ry + rx2;
+    while dx < dy {
+        visit(x, y);
+        x += 1;
+        dx += 2 * ry2;
+        if decision < 0 {
+            decision += 4 * (dx + ry2);
+        } else {
+            y -= 1;
+            dy -= 2 * rx2;
+            decision += 4 * (dx - dy + ry2);
+        }
+    }
+    // then step along y
+    let mut decision =
+        ry2 * (2 * x + 1) * (2 * x + 1) + 4 * rx2 * (y - 1) * (y - 1) - 4 * rx2 * ry2;
+    while y >= 0 {
+        visit(x, y);
+        y -= 1;
+        dy -= 2 * rx2;
+        if decision > 0 {
+            decision += 4 * (rx2 - dy);
+        } else {
+            x += 1;
+            dx += 2 * ry2;
+            decision += 4 * (dx - dy + rx2);
+        }
+    }
+    // very flat ellipses get down to the middle row before their slope gets steep, short of the tips
+    for x in x..=rx {
+        visit(x, 0);
+    }
+}
+
+/// Goes over the rows of an ellipse around row `centre_y` that are inside a target `height` rows high, calling
+/// `visit` with each row and how far either side of the centre the outline starts and ends on it. Unlike
+/// [midpoint_ellipse], it works the outline out a row at a time, so huge ellipses cost no more than the rows they
+/// cross, but it rounds a little differently.
+fn scanline_ellipse(
+    height: usize,
+    centre_y: i64,
+    radius_x: u32,
+    radius_y: u32,
+    mut visit: impl FnMut(i64, i64, i64),
+) {
+    let (rx, ry) = (radius_x as f64, radius_y as i64);
+    // where the outline is, `dy` rows above or below the centre
+    let crossing = |dy: f64| {
+        let t = dy / ry as f64;
+        rx * (1.0 - t * t).max(0.0).sqrt()
+    };
+    // how far either side of the centre the outline reaches on the row `dy` from the centre: as far as it gets
+    // before leaving the row where it's shallow, and the nearest pixel to it where it's steep, like the midpoint
+    // algorithm
+    let outer = |dy: i64| {
+        if dy == 0 {
+            radius_x as i64
+        } else {
+            (crossing(dy as f64 - 0.5) as i64).max((crossing(dy as f64) + 0.5) as i64)
+        }
+    };
+    for y in (centre_y - ry).max(0)..=(centre_y + ry).min(height as i64 - 1) {
+        let dy = (y - centre_y).abs();
+        // the outline carries on from where it left the row further out
+        let inner = if dy == ry {
+            0
+        } else {
+            (outer(dy + 1) + 1).min(outer(dy))
+        };
+        visit(y, inner, outer(dy));
+    }
+}
+
+/// Fills pixels `x0` to `x1` of row `y`, both ends included, after clipping them to the target. Positions are
+/// `i64`, so shapes can reach well past the target without overflowing.
+fn clipped_span<T: RenderTarget + ?Sized>(target: &mut T, x0: i64, x1: i64, y: i64, pixel: &Pixel) {
+    let (width, height) = target.size();
+    let (x0, x1) = (x0.max(0), x1.min(width as i64 - 1));
+    if !(0..height as i64).contains(&y) || x0 > x1 {
+        return;
+    }
+    if x0 == x1 {
+        target.plot_pixel(x0 as i32, y as i32, pixel);
+    } else {
+        target.fill_span(x0 as i32, y as i32, (x1 - x0 + 1) as usize, pixel);
+    }
+}
+
+/// Draws a `width` pixel wide line from `(x0, y0)` to `(x1, y1)`, ending in `cap`s. Lines a pixel wide or less
+/// are drawn with Bresenham's algorithm instead, and no caps.
+#[allow(clippy::too_many_arguments)]
+pub fn thick_line<T: RenderTarget + ?Sized>(
+    target: &mut T,
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+    width: f32,
+    cap: LineCap,
+    pixel: &Pixel,
+) {
+    stroke_segment(target, [x0, y0], [x1, y1], width, [cap, cap], pixel);
+}
+
+/// Draws a thick line for [RenderTarget::draw_thick_line], with coordinates relative to the centre of the target.
+pub(crate) fn centred_thick_line<T: RenderTarget + ?Sized>(
+    target: &mut T,
+    [x0, y0, x1, y1]: [f32; 4],
+    width: f32,
+    pixel: &Pixel,
+) {
+    // the middle of the pixel [centered] would put the point in
+    let (target_width, target_height) = target.size();
+    let (dx, dy) = (
+        (target_width / 2) as f32 + 0.5,
+        (target_height / 2) as f32 + 0.5,
+    );
+    let cap = LineCap::Round;
+    thick_line(
+        target,
+        x0 + dx,
+        y0 + dy,
+        x1 + dx,
+        y1 + dy,
+        width,
+        cap,
+        pixel,
+    );
+}
+
+/// Draws lines joining the points in order, `width` pixels wide, and back to the first point if `closed`. Joins
+/// are rounded so thick lines don't leave notches at corners, and open ends get `cap`s.
+pub fn polyline<T: RenderTarget + ?Sized>(
+    target: &mut T,
+    points: &[[f32; 2]],
+    width: f32,
+    cap: LineCap,
+    closed: bool,
+    pixel: &Pixel,
+) {
+    let segments = match points.len() {
+        0 => return,
+        1 => {
+            stroke_segment(target, points[0], points[0], width, [cap, cap], pixel);
+            return;
+        }
+        len if closed => len,
+        len => len - 1,
+    };
+    for idx in 0..segments {
+        // caps go on the open ends only
+        let start_cap = if !closed && idx == 0 {
+            cap
+        } else {
+            LineCap::Butt
+        };
+        let end_cap = if !closed && idx == segments - 1 {
+            cap
+        } else {
+            LineCap::Butt
+        };
+        let (from, to) = (points[idx], points[(idx + 1) % points.len()]);
+        stroke_segment(target, from, to, width, [start_cap, end_cap], pixel);
+    }
+    if width > 1.0 {
+        let joins = if closed {
+            points
+        } else {
+            &points[1..points.len() - 1]
+        };
+        for &[x, y] in joins {
+            fill_disc(target, x, y, width / 2.0, pixel);
+        }
+    }
+}
+
+/// Draws one line for [thick_line] or [polyline], with its own cap on each end.
+fn stroke_segment<T: RenderTarget + ?Sized>(
+    target: &mut T,
+    [x0, y0]: [f32; 2],
+    [x1, y1]: [f32; 2],
+    width: f32,
+    caps: [LineCap; 2],
+    pixel: &Pixel,
+) {
+    if width <= 1.0 {
+        bresenham(
+            floor(x0) as i32,
+            floor(y0) as i32,
+            floor(x1) as i32,
+            floor(y1) as i32,
+            |x, y| target.plot_pixel(x, y, pixel),
+        );
+        return;
+    }
+    let half = width / 2.0;
+    let (dx, dy) = (x1 - x0, y1 - y0);
+    let len = (dx * dx + dy * dy).sqrt();
+    if len < f32::EPSILON {
+        // no direction to draw in, so only the caps
+        if caps.contains(&LineCap::Round) {
+            fill_disc(target, x0, y0, half, pixel);
+        } else if caps.contains(&LineCap::Square) {
+            let corners = [[-half, -half], [half, -half], [half, half], [-half, half]];
+            fill_polygon(
+                target,
+                &corners.map(|[x, y]| [x0 + x, y0 + y]),
+                FillRule::NonZero,
+                pixel,
+            );
+        }
+        return;
+    }
+    // half the width along the line and across it
+    let (along_x, along_y) = (dx / len * half, dy / len * half);
+    let (across_x, across_y) = (-along_y, along_x);
+    let start = match caps[0] {
+        LineCap::Square => [x0 - along_x, y0 - along_y],
+        _ => [x0, y0],
+    };
+    let end = match caps[1] {
+        LineCap::Square => [x1 + along_x, y1 + along_y],
+        _ => [x1, y1],
+    };
+    fill_polygon(
+        target,
+        &[
+            [start[0] + across_x, start[1] + across_y],
+            [end[0] + across_x, end[1] + across_y],
+            [end[0] - across_x, end[1] - across_y],
+            [start[0] - across_x, start[1] - across_y],
+        ],
+        FillRule::NonZero,
+        pixel,
+    );
+    for (cap, [x, y]) in caps.into_iter().zip([[x0, y0], [x1, y1]]) {
+        if cap == LineCap::Round {
+            fill_disc(target, x, y, half, pixel);
+        }
+    }
+}
+
+/// Fills the polygon with corners at `points`, joined in order and back to the first. Pixels are filled when
+/// their centres are inside, by `rule`.
+pub fn fill_polygon<T: RenderTarget + ?Sized>(
+    target: &mut T,
+    points: &[[f32; 2]],
+    rule: FillRule,
+    pixel: &Pixel,
+) {
+    if points.len() < 3 {
+        return;
+    }
+    let (width, height) = target.size();
+    let (top, bottom) = points
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(top, bottom), point| {
+            (top.min(point[1]), bottom.max(point[1]))
+        });
+    let rows = pixel_span(top, bottom);
+    // where each edge crosses the row, and which way it's going
+    let mut crossings: Vec<(f32, i32)> = Vec::new();
+    for y in rows.0.max(0)..rows.1.min(height as i32) {
+        let centre = y as f32 + 0.5;
+        crossings.clear();
+        for (idx, &[x0, y0]) in points.iter().enumerate() {
+            let [x1, y1] = points[(idx + 1) % points.len()];
+            if (y0 <= centre) != (y1 <= centre) {
+                let x = x0 + (centre - y0) * (x1 - x0) / (y1 - y0);
+                crossings.push((x, if y1 > y0 { 1 } else { -1 }));
+            }
+        }
+        crossings.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+        let mut winding = 0;
+        for (idx, pair) in crossings.windows(2).enumerate() {
+            winding += pair[0].1;
+            let inside = match rule {
+                FillRule::EvenOdd => idx % 2 == 0,
+                FillRule::NonZero => winding != 0,
+            };
+            if inside {
+                let (start, end) = pixel_span(pair[0].0, pair[1].0);
+                let (start, end) = (start.max(0), end.min(width as i32));
+                if start < end {
+                    target.fill_span(start, y, (end - start) as usize, pixel);
+                }
+            }
+        }
+    }
+}
+
+/// Fills the disc of `radius` around the point. Unlike [fill_circle], it needn't be centred on a pixel.
+fn fill_disc<T: RenderTarget + ?Sized>(target: &mut T, x: f32, y: f32, radius: f32, pixel: &Pixel) {
+    let (width, height) = target.size();
+    let rows = pixel_span(y - radius, y + radius);
+    for row in rows.0.max(0)..rows.1.min(height as i32) {
+        let dy = row as f32 + 0.5 - y;
+        let half = (radius * radius - dy * dy).max(0.0).sqrt();
+        let (start, end) = pixel_span(x - half, x + half);
+        let (start, end) = (start.max(0), end.min(width as i32));
+        if start < end {
+            target.fill_span(start, row, (end - start) as usize, pixel);
+        }
+    }
+}
+
+/// The first pixel whose centre is at or after `from`, and the first whose centre is at or after `to`.
+fn pixel_span(from: f32, to: f32) -> (i32, i32) {
+    let first = |pos: f32| {
+        let pos = pos.clamp(i32::MIN as f32 / 2.0, i32::MAX as f32 / 2.0) - 0.5;
+        let whole = floor(pos);
+        (if whole < pos { whole + 1.0 } else { whole }) as i32
+    };
+    (first(from), first(to))
+}
+
+/// Rounds down. `f32::floor` needs std.
+fn floor(value: f32) -> f32 {
+    let whole = value as i32 as f32;
+    if whole > value {
+        whole - 1.0
+    } else {
+        whole
+    }
+}
+
+/// Draws a `width` by `height` rectangle with its top left corner at pixel `(x, y)`, in a `0xRRGGBB` colour,
+/// filled or outlined. See [rect] and [fill_rect].
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_draw_rect(
+    renderer: *mut Renderer,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    color: u32,
+    filled: bool,
+) {
+    let frame_buffer = (*renderer).buffer_mut();
+    let pixel = Pixel::from_hex(color);
+    if filled {
+        fill_rect(frame_buffer, x, y, width, height, &pixel);
+    } else {
+        rect(frame_buffer, x, y, width, height, &pixel);
+    }
+}
+
+/// Draws an ellipse around pixel `(centre_x, centre_y)` in a `0xRRGGBB` colour, filled or outlined. Give both
+/// radii the same for a circle. See [ellipse] and [fill_ellipse].
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_draw_ellipse(
+    renderer: *mut Renderer,
+    centre_x: i32,
+    centre_y: i32,
+    radius_x: u32,
+    radius_y: u32,
+    color: u32,
+    filled: bool,
+) {
+    let frame_buffer = (*renderer).buffer_mut();
+    let pixel = Pixel::from_hex(color);
+    if filled {
+        fill_ellipse(frame_buffer, centre_x, centre_y, radius_x, radius_y, &pixel);
+    } else {
+        ellipse(frame_buffer, centre_x, centre_y, radius_x, radius_y, &pixel);
+    }
+}
+
+/// Draws a `width` pixel wide line in a `0xRRGGBB` colour, in pixel coordinates from the top left corner, with
+/// caps picked by [LineCap::from_id]. Unknown caps are butt. See [thick_line].
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_draw_thick_line(
+    renderer: *mut Renderer,
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+    width: f32,
+    cap: u32,
+    color: u32,
+) {
+    let cap = LineCap::from_id(cap).unwrap_or_default();
+    thick_line(
+        (*renderer).buffer_mut(),
+        x0,
+        y0,
+        x1,
+        y1,
+        width,
+        cap,
+        &Pixel::from_hex(color),
+    );
+}
+
+/// Draws lines joining `count` points, each an x and a y, in a `0xRRGGBB` colour. See [polyline] and
+/// [mn_draw_thick_line].
+///
+/// # Safety
+/// `renderer` must come from [mn_create] and `points` must point to `count * 2` floats.
+#[no_mangle]
+pub unsafe extern "C" fn mn_draw_polyline(
+    renderer: *mut Renderer,
+    points: *const f32,
+    count: u32,
+    width: f32,
+    cap: u32,
+    closed: bool,
+    color: u32,
+) {
+    let cap = LineCap::from_id(cap).unwrap_or_default();
+    polyline(
+        (*renderer).buffer_mut(),
+        points_from_raw(points, count),
+        width,
+        cap,
+        closed,
+        &Pixel::from_hex(color),
+    );
+}
+
+/// Fills the polygon with `count` corners, each an x and a y, in a `0xRRGGBB` colour, by the rule picked by
+/// [FillRule::from_id]. Unknown rules are even-odd. See [fill_polygon].
+///
+/// # Safety
+/// `renderer` must come from [mn_create] and `points` must point to `count * 2` floats.
+#[no_mangle]
+pub unsafe extern "C" fn mn_fill_polygon(
+    renderer: *mut Renderer,
+    points: *const f32,
+    count: u32,
+    rule: u32,
+    color: u32,
+) {
+    let rule = FillRule::from_id(rule).unwrap_or_default();
+    fill_polygon(
+        (*renderer).buffer_mut(),
+        points_from_raw(points, count),
+        rule,
+        &Pixel::from_hex(color),
+    );
+}
+
+/// # Safety
+/// `points` must be null or point to `count * 2` floats.
+unsafe fn points_from_raw<'a>(points: *const f32, count: u32) -> &'a [[f32; 2]] {
+    if points.is_null() {
+        return &[];
+    }
+    core::slice::from_raw_parts(points.cast(), count as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Draws into a blank 9 by 7 frame buffer and shows it as rows of `#` for drawn pixels and `.` for the rest.
+    fn drawn(draw: impl FnOnce(&mut FrameBuffer)) -> Vec<String> {
+        let mut frame_buffer = FrameBuffer::new(9, 7);
+        draw(&mut frame_buffer);
+        frame_buffer
+            .pixels()
+            .chunks(9)
+            .map(|row| {
+                row.iter()
+                    .map(|pixel| if pixel.a == 0 { '.' } else { '#' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn outline(radius_x: u32, radius_y: u32) -> Vec<String> {
+        drawn(|frame_buffer| ellipse(frame_buffer, 4, 3, radius_x, radius_y, &Pixel::WHITE))
+    }
+
+    fn filled(radius_x: u32, radius_y: u32) -> Vec<String> {
+        drawn(|frame_buffer| fill_ellipse(frame_buffer, 4, 3, radius_x, radius_y, &Pixel::WHITE))
+    }
+
+    #[test]
+    fn rects() {
+        let white = &Pixel::WHITE;
+        assert_eq!(
+            drawn(|frame_buffer| rect(frame_buffer, 1, 1, 4, 3, white)),
+            [
+                ".........",
+                ".####....",
+                ".#..#....",
+                ".####....",
+                ".........",
+                ".........",
+                ".........",
+            ]
+        );
+        assert_eq!(
+            drawn(|frame_buffer| fill_rect(frame_buffer, 6, -2, 5, 4, white)),
+            [
+                "......###",
+                "......###",
+                ".........",
+                ".........",
+                ".........",
+                ".........",
+                ".........",
+            ]
+        );
+    }
+
+    #[test]
+    fn huge_rects() {
+        let white = &Pixel::WHITE;
+        // sizes that would overflow an i32, and so many rows the outline has to skip the clipped ones
+        assert_eq!(
+            drawn(|frame_buffer| rect(frame_buffer, 2, 3, u32::MAX, u32::MAX, white)),
+            [
+                ".........",
+                ".........",
+                ".........",
+                "..#######",
+                "..#......",
+                "..#......",
+                "..#......",
+            ]
+        );
+        assert_eq!(
+            drawn(|frame_buffer| rect(frame_buffer, -100, i32::MIN, 109, u32::MAX, white)),
+            [
+                "........#",
+                "........#",
+                "........#",
+                "........#",
+                "........#",
+                "........#",
+                "........#",
+            ]
+        );
+        assert_eq!(
+            drawn(|frame_buffer| rect(frame_buffer, i32::MAX, i32::MAX, u32::MAX, u32::MAX, white)),
+            [
+                ".........",
+                ".........",
+                ".........",
+                ".........",
+                ".........",
+                ".........",
+                ".........",
+            ]
+        );
+        assert!(drawn(|frame_buffer| fill_rect(
+            frame_buffer,
+            -5,
+            i32::MIN,
+            u32::MAX,
+            u32::MAX,
+            white
+        ))
+        .iter()
+        .all(|row| row == "#########"));
+        assert!(drawn(|frame_buffer| fill_rect(
+            frame_buffer,
+            i32::MAX,
+            0,
+            u32::MAX,
+            u32::MAX,
+            white
+        ))
+        .iter()
+        .all(|row| row == "........."));
+    }
+
+    #[test]
+    fn small_ellipses() {
+        let dot = [
+            ".........",
+            ".........",
+            ".........",
+            "....#....",
+            ".........",
+            ".........",
+            ".........",
+        ];
+        assert_eq!(outline(0, 0), dot);
+        assert_eq!(filled(0, 0), dot);
+        assert_eq!(
+            outline(1, 1),
+            [
+                ".........",
+                ".........",
+                "....#....",
+                "...#.#...",
+                "....#....",
+                ".........",
+                ".........",
+            ]
+        );
+        assert_eq!(
+            filled(1, 1),
+            [
+                ".........",
+                ".........",
+                "....#....",
+                "...###...",
+                "....#....",
+                ".........",
+                ".........",
+            ]
+        );
+        assert_eq!(
+            outline(2, 2),
+            [
+                ".........",
+                "...###...",
+                "..#...#..",
+                "..#...#..",
+                "..#...#..",
+                "...###...",
+                ".........",
+            ]
+        );
+        assert_eq!(
+            filled(2, 2),
+            [
+                ".........",
+                "...###...",
+                "..#####..",
+                "..#####..",
+                "..#####..",
+                "...###...",
+                ".........",
+            ]
+        );
+        assert_eq!(
+            outline(3, 1),
+            [
+                ".........",
+                ".........",
+                "..#####..",
+                ".#.....#.",
+                "..#####..",
+                ".........",
+                ".........",
+            ]
+        );
+        assert_eq!(
+            filled(1, 3),
+            [
+                "....#....",
+                "...###...",
+                "...###...",
+                "...###...",
+                "...###...",
+                "...###...",
+                "....#....",
+            ]
+        );
+        assert_eq!(
+            outline(4, 0),
+            [
+                ".........",
+                ".........",
+                ".........",
+                "#########",
+                ".........",
+                ".........",
+                ".........",
+            ]
+        );
+    }
+
+    #[test]
+    fn flat_ellipses_reach_their_tips() {
+        for radius_x in 1..200 {
+            let mut last = -1;
+            midpoint_ellipse(radius_x, 1, |x, y| {
+                if y == 0 {
+                    last = last.max(x);
+                }
+            });
+            assert_eq!(last, radius_x as i64);
+        }
+    }
+
+    #[test]
+    fn scanline_ellipses_match_midpoint() {
+        // how far each row reaches, from the middle row out
+        for radius_x in 0..60 {
+            for radius_y in 0..60 {
+                let mut midpoint = vec![0; radius_y as usize + 1];
+                midpoint_ellipse(radius_x, radius_y, |x, y| {
+                    midpoint[y as usize] = midpoint[y as usize].max(x)
+                });
+                let mut scanline = vec![0; radius_y as usize + 1];
+                let centre = radius_y as i64;
+                scanline_ellipse(
+                    2 * radius_y as usize + 1,
+                    centre,
+                    radius_x,
+                    radius_y,
+                    |y, _, outer| scanline[(y - centre).unsigned_abs() as usize] = outer,
+                );
+                for (midpoint, scanline) in midpoint.iter().zip(&scanline) {
+                    assert!((midpoint - scanline).abs() <= 1, "{radius_x} by {radius_y}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn huge_ellipses() {
+        let mut frame_buffer = FrameBuffer::new(100, 100);
+        ellipse(
+            &mut frame_buffer,
+            50,
+            50,
+            3_000_000,
+            3_000_000,
+            &Pixel::WHITE,
+        );
+        assert_eq!(frame_buffer.drawn(), None);
+        fill_ellipse(
+            &mut frame_buffer,
+            50,
+            50,
+            3_000_000,
+            3_000_000,
+            &Pixel::WHITE,
+        );
+        assert_eq!(frame_buffer.drawn(), Some(Rect::full(100, 100)));
+
+        // only the rows above and below the middle one are near enough to the outline
+        let mut frame_buffer = FrameBuffer::new(100, 100);
+        ellipse(&mut frame_buffer, 50, 50, 200_000_000, 1, &Pixel::WHITE);
+        let rows: Vec<bool> = (48..53)
+            .map(|y| (0..100).all(|x| frame_buffer.pixel(x, y) == Some(Pixel::WHITE)))
+            .collect();
+        assert_eq!(rows, [false, true, false, true, false]);
+
+        let mut frame_buffer = FrameBuffer::new(100, 100);
+        fill_ellipse(
+            &mut frame_buffer,
+            i32::MAX,
+            i32::MIN,
+            u32::MAX,
+            u32::MAX,
+            &Pixel::WHITE,
+        );
+        ellipse(&mut frame_buffer, i32::MAX, 0, 10, 10, &Pixel::WHITE);
+        assert_eq!(frame_buffer.drawn(), Some(Rect::full(100, 100)));
+    }
+
+    #[test]
+    fn fill_rules() {
+        // a square inside another, going round the same way, so the inner one is wound twice
+        let squares = [
+            [0.0, 0.0],
+            [9.0, 0.0],
+            [9.0, 7.0],
+            [0.0, 7.0],
+            [0.0, 0.0],
+            [3.0, 2.0],
+            [6.0, 2.0],
+            [6.0, 5.0],
+            [3.0, 5.0],
+            [3.0, 2.0],
+        ];
+        let fill =
+            |rule| drawn(|frame_buffer| fill_polygon(frame_buffer, &squares, rule, &Pixel::WHITE));
+        assert_eq!(
+            fill(FillRule::EvenOdd),
+            [
+                "#########",
+                "#########",
+                "###...###",
+                "###...###",
+                "###...###",
+                "#########",
+                "#########",
+            ]
+        );
+        assert_eq!(fill(FillRule::NonZero), ["#########"; 7]);
+    }
+}
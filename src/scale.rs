@@ -0,0 +1,329 @@
+//! Scaling frame buffers to other sizes, for screens that aren't the renderer's size. The picture can be fitted
+//! inside the screen keeping its shape, with bars filling the rest (letterboxed above and below, or pillarboxed
+//! either side), stretched over all of it, or blown up by a whole number for crisp pixels. Rows are produced one
+//! at a time, so they can go straight into whatever the screen wants, like [crate::yuyv].
+//!
+//! ```
+//! use meenle_noonle::scale::{Filter, ScaleMode, Scaler};
+//!
+//! // a square picture on a 4:3 screen gets bars either side
+//! let scaler = Scaler::new((500, 500), (640, 480), ScaleMode::Fit, Filter::Bilinear);
+//! assert_eq!(scaler.picture(), meenle_noonle::Rect { x: 80, y: 0, width: 480, height: 480 });
+//! ```
+
+use core::ops::Range;
+
+use crate::*;
+
+/// How the picture is sized to the destination.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScaleMode {
+    /// As large as fits, keeping its shape. Bars fill the rest.
+    #[default]
+    Fit,
+    /// Over the whole destination, whatever its shape.
+    Stretch,
+    /// The largest whole number of times that fits, at least once, and centred. Pictures bigger than the
+    /// destination are cropped.
+    Integer,
+}
+
+/// How pixels in between source pixels are coloured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Filter {
+    /// The closest source pixel. Blocky, and fast.
+    #[default]
+    Nearest,
+    /// A blend of the four closest source pixels.
+    Bilinear,
+}
+
+/// Where a destination column or row samples the source: two neighbouring pixels, and how much of the second,
+/// out of 256.
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    idx: usize,
+    next: usize,
+    weight: u32,
+}
+
+/// Maps a source frame buffer of one size onto a destination of another.
+#[derive(Debug, Clone)]
+pub struct Scaler {
+    source: (usize, usize),
+    destination: (usize, usize),
+    /// Where the picture's top left corner lands in the destination. Negative when it's cropped.
+    offset: (isize, isize),
+    /// Size of the scaled picture, before any cropping.
+    size: (usize, usize),
+    filter: Filter,
+    /// Colour of the bars around the picture.
+    pub bars: Pixel,
+    /// Samples for every destination column, [None] in the bars.
+    columns: Vec<Option<Sample>>,
+}
+
+impl Scaler {
+    /// Works out where a `source` sized picture goes in a `destination` sized one. Bars are black.
+    pub fn new(
+        source: (usize, usize),
+        destination: (usize, usize),
+        mode: ScaleMode,
+        filter: Filter,
+    ) -> Scaler {
+        let size = match mode {
+            ScaleMode::Stretch => destination,
+            ScaleMode::Fit => {
+                // compare the aspect ratios without dividing
+                let (source_width, source_height) = (source.0.max(1), source.1.max(1));
+                if destination.0 * source_height <= destination.1 * source_width {
+                    (destination.0, destination.0 * source_height / source_width)
+                } else {
+                    (destination.1 * source_width / source_height, destination.1)
+                }
+            }
+            ScaleMode::Integer => {
+                let times = (destination.0 / source.0.max(1))
+                    .min(destination.1 / source.1.max(1))
+                    .max(1);
+                (source.0 * times, source.1 * times)
+            }
+        };
+        let offset = (
+            (destination.0 as isize - size.0 as isize) / 2,
+            (destination.1 as isize - size.1 as isize) / 2,
+        );
+        let columns = (0..destination.0)
+            .map(|x| sample(filter, x as isize - offset.0, size.0, source.0))
+            .collect();
+        Scaler {
+            source,
+            destination,
+            offset,
+            size,
+            filter,
+            bars: Pixel::rgb(0, 0, 0),
+            columns,
+        }
+    }
+
+    /// The part of the destination the picture covers. Everything else is bars.
+    pub fn picture(&self) -> Rect {
+        let clip = |offset: isize, size: usize, len: usize| {
+            let start = offset.clamp(0, len as isize) as usize;
+            let end = (offset + size as isize).clamp(0, len as isize) as usize;
+            (start as u32, (end - start) as u32)
+        };
+        let (x, width) = clip(self.offset.0, self.size.0, self.destination.0);
+        let (y, height) = clip(self.offset.1, self.size.1, self.destination.1);
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// The destination rows that change when `source_rows` of the source do, for only redrawing what's dirty.
+    /// Ranges reaching the top or bottom of the source take the bars above or below the picture with them.
+    pub fn destination_rows(&self, source_rows: Range<usize>) -> Range<usize> {
+        if source_rows.is_empty() || self.source.1 == 0 {
+            return 0..0;
+        }
+        let height = self.destination.1 as isize;
+        // going down the picture, rows only ever sample further down the source, so the ones sampling any of
+        // `source_rows`, either as the row they start from or the one they blend towards, are a single run
+        let sampled = |pos: usize| sample(self.filter, pos as isize, self.size.1, self.source.1);
+        let first = partition_point(self.size.1, |pos| {
+            sampled(pos).is_some_and(|sample| sample.next < source_rows.start)
+        });
+        let last = partition_point(self.size.1, |pos| {
+            sampled(pos).is_some_and(|sample| sample.idx < source_rows.end)
+        });
+        let start = if source_rows.start == 0 {
+            0
+        } else {
+            self.offset.1 + first as isize
+        };
+        let end = if source_rows.end >= self.source.1 {
+            height
+        } else {
+            self.offset.1 + last as isize
+        };
+        let (start, end) = (
+            start.clamp(0, height) as usize,
+            end.clamp(0, height) as usize,
+        );
+        start..end.max(start)
+    }
+
+    /// Scales `rows` of the destination, handing each one to `row` along with its index. The source must be the
+    /// size the scaler was made for.
+    pub fn scale_rows(
+        &self,
+        source: &FrameBuffer,
+        rows: Range<usize>,
+        mut row: impl FnMut(usize, &[Pixel]),
+    ) {
+        let pixels = source.pixels();
+        let width = self.source.0;
+        let mut out = vec![self.bars; self.destination.0];
+        for idx_row in rows.start..rows.end.min(self.destination.1) {
+            let Some(sample_row) = sample(
+                self.filter,
+                idx_row as isize - self.offset.1,
+                self.size.1,
+                self.source.1,
+            ) else {
+                out.fill(self.bars);
+                row(idx_row, &out);
+                continue;
+            };
+            let (above, below) = (
+                &pixels[sample_row.idx * width..][..width],
+                &pixels[sample_row.next * width..][..width],
+            );
+            for (pxl, column) in out.iter_mut().zip(&self.columns) {
+                *pxl = match column {
+                    None => self.bars,
+                    Some(column) => {
+                        let top = blend(above[column.idx], above[column.next], column.weight);
+                        let bottom = blend(below[column.idx], below[column.next], column.weight);
+                        blend(top, bottom, sample_row.weight)
+                    }
+                };
+            }
+            row(idx_row, &out);
+        }
+    }
+
+    /// Scales the whole source into the destination, which must be the size the scaler was made for.
+    pub fn scale<F: PixelFormat>(&self, source: &FrameBuffer, destination: &mut FrameBuffer<F>) {
+        let stride = destination.stride();
+        let bytes = destination.bytes_mut();
+        self.scale_rows(source, 0..self.destination.1, |idx_row, row| {
+            F::encode_row(row, &mut bytes[idx_row * stride..(idx_row + 1) * stride]);
+        });
+    }
+}
+
+/// Where position `pos` of a `picture_len` long picture samples a `source_len` long source, with pixel centres
+/// lined up. [None] outside the picture.
+fn sample(filter: Filter, pos: isize, picture_len: usize, source_len: usize) -> Option<Sample> {
+    if pos < 0 || pos as usize >= picture_len || source_len == 0 {
+        return None;
+    }
+    let last = source_len - 1;
+    // the pixel's centre in the source, in 256ths of a pixel
+    let centre = (2 * pos as usize + 1) * source_len * 256 / (2 * picture_len);
+    Some(match filter {
+        Filter::Nearest => {
+            let idx = (centre / 256).min(last);
+            Sample {
+                idx,
+                next: idx,
+                weight: 0,
+            }
+        }
+        Filter::Bilinear => {
+            // blend between the source pixel centres either side
+            let from_first = centre.saturating_sub(128);
+            let idx = (from_first / 256).min(last);
+            Sample {
+                idx,
+                next: (idx + 1).min(last),
+                weight: (from_first % 256) as u32,
+            }
+        }
+    })
+}
+
+/// The first of `0..len` that `before` is false for, given it's true for everything up to some point and false
+/// after.
+fn partition_point(len: usize, before: impl Fn(usize) -> bool) -> usize {
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if before(middle) {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+    low
+}
+
+/// Mixes in `weight` 256ths of `to`.
+fn blend(from: Pixel, to: Pixel, weight: u32) -> Pixel {
+    if weight == 0 {
+        return from;
+    }
+    let mix = |a: u8, b: u8| ((a as u32 * (256 - weight) + b as u32 * weight + 128) >> 8) as u8;
+    Pixel {
+        r: mix(from.r, to.r),
+        g: mix(from.g, to.g),
+        b: mix(from.b, to.b),
+        a: mix(from.a, to.a),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A picture where every pixel differs from its neighbours, changed all over by `seed`.
+    fn pattern(width: usize, height: usize, seed: u32) -> FrameBuffer {
+        let mut frame_buffer = FrameBuffer::new(width, height);
+        for (idx, pixel) in frame_buffer.pixels_mut().iter_mut().enumerate() {
+            *pixel = Pixel::from_hex((idx as u32 + seed).wrapping_mul(0x9e37_79b9) >> 8);
+        }
+        frame_buffer
+    }
+
+    /// Redraws each source row in turn, only scaling the destination rows it maps to, and checks the result
+    /// matches scaling everything.
+    fn check_partial(source: (usize, usize), destination: (usize, usize)) {
+        for filter in [Filter::Nearest, Filter::Bilinear] {
+            for mode in [ScaleMode::Fit, ScaleMode::Stretch, ScaleMode::Integer] {
+                let scaler = Scaler::new(source, destination, mode, filter);
+                let before = pattern(source.0, source.1, 0);
+                for dirty in 0..source.1 {
+                    let mut partial = FrameBuffer::new(destination.0, destination.1);
+                    scaler.scale(&before, &mut partial);
+                    // only the dirty row changes
+                    let mut after = before.clone();
+                    let changed = pattern(source.0, source.1, 12345);
+                    let row = dirty * source.0..(dirty + 1) * source.0;
+                    after.pixels_mut()[row.clone()].copy_from_slice(&changed.pixels()[row]);
+
+                    let rows = scaler.destination_rows(dirty..dirty + 1);
+                    let width = destination.0;
+                    let pixels = partial.pixels_mut();
+                    scaler.scale_rows(&after, rows, |idx_row, row| {
+                        pixels[idx_row * width..(idx_row + 1) * width].copy_from_slice(row);
+                    });
+                    let mut full = FrameBuffer::new(destination.0, destination.1);
+                    scaler.scale(&after, &mut full);
+                    assert!(
+                        partial.pixels() == full.pixels(),
+                        "row {dirty} of {source:?} to {destination:?}, {mode:?}, {filter:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn partial_scaling_matches_full() {
+        check_partial((16, 12), (16, 12));
+        check_partial((16, 12), (32, 24));
+        check_partial((16, 12), (48, 36));
+        check_partial((16, 12), (64, 48));
+        // with bars above and below, and either side
+        check_partial((16, 12), (64, 60));
+        check_partial((16, 12), (80, 48));
+        // and down
+        check_partial((40, 30), (16, 12));
+        check_partial((40, 30), (23, 17));
+    }
+}
@@ -0,0 +1,398 @@
+//! Skeletal animation. A [Skeleton] is a hierarchy of joints, each posed with a rotation relative to its parent.
+//! A [SkinnedMesh] pairs a bind pose [Mesh] with per-vertex joint indices and weights, and [SkinnedMesh::pose]
+//! deforms it with linear blend skinning on the CPU, before it is rasterized like any other mesh.
+//!
+//! From C, skeletons and skinned meshes are created with [mn_skeleton_new] and [mn_skinned_mesh_new], which
+//! return opaque pointers that must be released with [mn_skeleton_free] and [mn_skinned_mesh_free].
+
+#[cfg(target_arch = "powerpc")]
+use {alloc::boxed::Box, alloc::vec::Vec};
+
+use crate::*;
+
+/// Number of joints that can influence a single vertex.
+pub const MAX_INFLUENCES: usize = 4;
+
+/// Joint indices and their weights for one vertex. Weights don't need to sum to one, they are normalized when
+/// the mesh is posed. Unused influences should have a weight of zero.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SkinWeights {
+    pub joints: [u32; MAX_INFLUENCES],
+    pub weights: [f32; MAX_INFLUENCES],
+}
+
+impl SkinWeights {
+    /// The vertex follows a single joint.
+    pub const fn single(joint: u32) -> SkinWeights {
+        SkinWeights {
+            joints: [joint, 0, 0, 0],
+            weights: [1.0, 0.0, 0.0, 0.0],
+        }
+    }
+
+    /// The vertex is blended between two joints, `t` being the share of the second one.
+    pub fn blend(joint_a: u32, joint_b: u32, t: f32) -> SkinWeights {
+        SkinWeights {
+            joints: [joint_a, joint_b, 0, 0],
+            weights: [1.0 - t, t, 0.0, 0.0],
+        }
+    }
+}
+
+/// A bone of a [Skeleton].
+#[derive(Debug, Clone)]
+pub struct Joint {
+    /// Index of the parent joint. Parents always come before their children.
+    pub parent: Option<usize>,
+    /// Position of the joint in the bind pose, in model space.
+    pub head: Vec3,
+    /// Current pose: rotation about the x, y and z axes, relative to the parent.
+    pub rot: Vec3,
+}
+
+/// Rotation and translation of a posed joint.
+#[derive(Debug, Clone, Copy)]
+struct JointTransform {
+    rot: Mat3x3,
+    loc: Vec3,
+}
+
+/// Bone hierarchy driving a [SkinnedMesh].
+#[derive(Debug, Clone, Default)]
+pub struct Skeleton {
+    pub joints: Vec<Joint>,
+}
+
+impl Skeleton {
+    pub fn new() -> Skeleton {
+        Skeleton::default()
+    }
+
+    /// Adds a joint at `head` (bind pose, model space) and returns its index. Returns [None] if the parent
+    /// doesn't exist yet.
+    pub fn add_joint(&mut self, parent: Option<usize>, head: Vec3) -> Option<usize> {
+        if parent.is_some_and(|parent| parent >= self.joints.len()) {
+            return None;
+        }
+        self.joints.push(Joint {
+            parent,
+            head,
+            rot: Vec3::from([0.0, 0.0, 0.0]),
+        });
+        Some(self.joints.len() - 1)
+    }
+
+    /// Poses a joint. Out of range joints are ignored.
+    pub fn set_pose(&mut self, joint: usize, rot: Vec3) {
+        if let Some(joint) = self.joints.get_mut(joint) {
+            joint.rot = rot;
+        }
+    }
+
+    /// Puts every joint back in its bind pose.
+    pub fn reset_pose(&mut self) {
+        for joint in &mut self.joints {
+            joint.rot = Vec3::from([0.0, 0.0, 0.0]);
+        }
+    }
+
+    /// Transforms taking a bind pose vertex attached to each joint to its posed position.
+    fn skinning_transforms(&self) -> Vec<JointTransform> {
+        let mut posed: Vec<JointTransform> = Vec::with_capacity(self.joints.len());
+        for joint in &self.joints {
+            let local = Mat3x3::rot(joint.rot.z, Axis::Z)
+                * Mat3x3::rot(joint.rot.y, Axis::Y)
+                * Mat3x3::rot(joint.rot.x, Axis::X);
+            posed.push(match joint.parent {
+                Some(parent) => {
+                    let parent_head = self.joints[parent].head;
+                    let parent = posed[parent];
+                    JointTransform {
+                        rot: parent.rot * local,
+                        loc: parent.rot * (joint.head - parent_head) + parent.loc,
+                    }
+                }
+                None => JointTransform {
+                    rot: local,
+                    loc: joint.head,
+                },
+            });
+        }
+        // the bind pose has no rotation, so undoing it only needs the joint's head subtracted
+        for (transform, joint) in posed.iter_mut().zip(&self.joints) {
+            transform.loc = transform.loc - transform.rot * joint.head;
+        }
+        posed
+    }
+}
+
+/// Mesh deformed by a [Skeleton].
+#[derive(Debug, Clone)]
+pub struct SkinnedMesh {
+    /// The undeformed mesh.
+    pub bind: Mesh,
+    /// Skin weights for each vertex of each triangle of [SkinnedMesh::bind].
+    pub weights: Vec<[SkinWeights; 3]>,
+}
+
+impl SkinnedMesh {
+    /// Returns [None] if there isn't exactly one set of weights per triangle vertex.
+    pub fn new(bind: Mesh, weights: Vec<[SkinWeights; 3]>) -> Option<SkinnedMesh> {
+        (bind.tris.len() == weights.len()).then_some(SkinnedMesh { bind, weights })
+    }
+
+    /// Deforms the bind pose according to the skeleton's current pose. Influences naming joints the skeleton
+    /// doesn't have are ignored.
+    pub fn pose(&self, skeleton: &Skeleton) -> Mesh {
+        let transforms = skeleton.skinning_transforms();
+        let mut posed = self.bind.clone();
+        for (tri, tri_weights) in posed.tris.iter_mut().zip(&self.weights) {
+            for (vert, skin) in tri.verts.iter_mut().zip(tri_weights) {
+                let mut sum = Vec3::from([0.0, 0.0, 0.0]);
+                let mut total_weight = 0.0;
+                for (&joint, &weight) in skin.joints.iter().zip(&skin.weights) {
+                    if let Some(transform) = transforms.get(joint as usize) {
+                        sum = sum + weight * (transform.rot * *vert + transform.loc);
+                        total_weight += weight;
+                    }
+                }
+                if total_weight > 0.0 {
+                    *vert = (1.0 / total_weight) * sum;
+                }
+            }
+        }
+        posed
+    }
+}
+
+/// Creates an empty skeleton. Free it with [mn_skeleton_free].
+#[no_mangle]
+pub extern "C" fn mn_skeleton_new() -> *mut Skeleton {
+    Box::into_raw(Box::new(Skeleton::new()))
+}
+
+/// Frees a skeleton created by [mn_skeleton_new].
+///
+/// # Safety
+/// `skeleton` must come from [mn_skeleton_new] and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn mn_skeleton_free(skeleton: *mut Skeleton) {
+    if !skeleton.is_null() {
+        drop(Box::from_raw(skeleton));
+    }
+}
+
+/// Adds a joint to the skeleton and returns its index, or -1 if `parent` isn't a joint yet. Pass a negative
+/// `parent` for a root joint.
+///
+/// # Safety
+/// `skeleton` must come from [mn_skeleton_new].
+#[no_mangle]
+pub unsafe extern "C" fn mn_skeleton_add_joint(
+    skeleton: *mut Skeleton,
+    parent: i32,
+    x: f32,
+    y: f32,
+    z: f32,
+) -> i32 {
+    let parent = usize::try_from(parent).ok();
+    match (*skeleton).add_joint(parent, Vec3::from([x, y, z])) {
+        Some(joint) => joint as i32,
+        None => -1,
+    }
+}
+
+/// Poses a joint, relative to its parent.
+///
+/// # Safety
+/// `skeleton` must come from [mn_skeleton_new].
+#[no_mangle]
+pub unsafe extern "C" fn mn_skeleton_set_pose(
+    skeleton: *mut Skeleton,
+    joint: u32,
+    x_angle: f32,
+    y_angle: f32,
+    z_angle: f32,
+) {
+    (*skeleton).set_pose(joint as usize, Vec3::from([x_angle, y_angle, z_angle]));
+}
+
+/// Creates a skinned mesh from `tri_count` triangles and `tri_count * 3` skin weights, one per vertex. The data is
+/// copied. Returns null if either pointer is null. Free it with [mn_skinned_mesh_free].
+///
+/// # Safety
+/// `tris` and `weights` must point to that many valid elements.
+#[no_mangle]
+pub unsafe extern "C" fn mn_skinned_mesh_new(
+    tris: *const Tri,
+    weights: *const SkinWeights,
+    tri_count: u32,
+) -> *mut SkinnedMesh {
+    if tris.is_null() || weights.is_null() {
+        return core::ptr::null_mut();
+    }
+    let tris = core::slice::from_raw_parts(tris, tri_count as usize);
+    let weights = core::slice::from_raw_parts(weights, tri_count as usize * 3);
+    let weights = weights
+        .chunks_exact(3)
+        .map(|vert_weights| [vert_weights[0], vert_weights[1], vert_weights[2]])
+        .collect();
+    match SkinnedMesh::new(Mesh::from(Vec::from(tris)), weights) {
+        Some(skinned_mesh) => Box::into_raw(Box::new(skinned_mesh)),
+        None => core::ptr::null_mut(),
+    }
+}
+
+/// Frees a skinned mesh created by [mn_skinned_mesh_new].
+///
+/// # Safety
+/// `skinned_mesh` must come from [mn_skinned_mesh_new] and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn mn_skinned_mesh_free(skinned_mesh: *mut SkinnedMesh) {
+    if !skinned_mesh.is_null() {
+        drop(Box::from_raw(skinned_mesh));
+    }
+}
+
+/// Renders a skinned mesh, posed by the skeleton, into the renderer's frame buffer.
+///
+/// # Safety
+/// `renderer`, `skinned_mesh` and `skeleton` must come from [mn_create], [mn_skinned_mesh_new] and
+/// [mn_skeleton_new].
+#[no_mangle]
+pub unsafe extern "C" fn mn_render_skinned(
+    renderer: *mut Renderer,
+    skinned_mesh: *const SkinnedMesh,
+    skeleton: *const Skeleton,
+    scalar: f32,
+    x_angle: f32,
+    y_angle: f32,
+    z_angle: f32,
+) {
+    let mut mesh = (*skinned_mesh).pose(&*skeleton);
+    mesh.scale(scalar);
+
+    mesh.rot(Axis::X, x_angle);
+    mesh.rot(Axis::Y, y_angle);
+    mesh.rot(Axis::Z, z_angle);
+
+    (*renderer).render_mesh(&mesh);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::TAU;
+
+    /// A root joint at the origin with a child 10 along x, and one triangle out past the child, tied to it.
+    fn arm() -> (Skeleton, Mesh) {
+        let mut skeleton = Skeleton::new();
+        let root = skeleton.add_joint(None, Vec3::from([0.0, 0.0, 0.0]));
+        skeleton.add_joint(root, Vec3::from([10.0, 0.0, 0.0]));
+        let tri = Tri {
+            verts: [
+                Vec3::from([20.0, 0.0, 0.0]),
+                Vec3::from([15.0, 0.0, 0.0]),
+                Vec3::from([20.0, 0.0, 5.0]),
+            ],
+        };
+        (skeleton, Mesh::from(vec![tri]))
+    }
+
+    fn assert_near(vert: Vec3, expected: [f32; 3]) {
+        let error = [
+            vert.x - expected[0],
+            vert.y - expected[1],
+            vert.z - expected[2],
+        ];
+        assert!(
+            error.iter().all(|error| error.abs() < 1e-4),
+            "{vert:?} isn't {expected:?}"
+        );
+    }
+
+    #[test]
+    fn rest_pose_is_bind_pose() {
+        let (skeleton, bind) = arm();
+        let weights = vec![[
+            SkinWeights::single(1),
+            SkinWeights::blend(0, 1, 0.3),
+            SkinWeights::single(0),
+        ]];
+        let posed = SkinnedMesh::new(bind.clone(), weights)
+            .unwrap()
+            .pose(&skeleton);
+        for (posed, bind) in posed.tris[0].verts.iter().zip(bind.tris[0].verts) {
+            assert_near(*posed, [bind.x, bind.y, bind.z]);
+        }
+    }
+
+    #[test]
+    fn rotated_joint() {
+        let (mut skeleton, bind) = arm();
+        let skinned_mesh = SkinnedMesh::new(bind, vec![[SkinWeights::single(1); 3]]).unwrap();
+        // a quarter turn about z swings x round into y, about the child's head
+        skeleton.set_pose(1, Vec3::from([0.0, 0.0, TAU / 4.0]));
+        let posed = skinned_mesh.pose(&skeleton);
+        assert_near(posed.tris[0].verts[0], [10.0, 10.0, 0.0]);
+        assert_near(posed.tris[0].verts[1], [10.0, 5.0, 0.0]);
+        assert_near(posed.tris[0].verts[2], [10.0, 10.0, 5.0]);
+
+        // children follow their parents
+        skeleton.reset_pose();
+        skeleton.set_pose(0, Vec3::from([0.0, 0.0, TAU / 4.0]));
+        assert_near(
+            skinned_mesh.pose(&skeleton).tris[0].verts[0],
+            [0.0, 20.0, 0.0],
+        );
+    }
+
+    #[test]
+    fn blended_vertex_lands_halfway() {
+        let (mut skeleton, bind) = arm();
+        let skinned_mesh =
+            SkinnedMesh::new(bind, vec![[SkinWeights::blend(0, 1, 0.5); 3]]).unwrap();
+        skeleton.set_pose(1, Vec3::from([0.0, 0.0, TAU / 4.0]));
+        // halfway between where the root leaves it, (20, 0, 0), and where the child takes it, (10, 10, 0)
+        assert_near(
+            skinned_mesh.pose(&skeleton).tris[0].verts[0],
+            [15.0, 5.0, 0.0],
+        );
+    }
+
+    #[test]
+    fn weights_must_match_triangles() {
+        let (_, bind) = arm();
+        assert!(SkinnedMesh::new(bind.clone(), Vec::new()).is_none());
+        assert!(SkinnedMesh::new(bind, vec![[SkinWeights::single(0); 3]; 2]).is_none());
+    }
+
+    #[test]
+    fn exports() {
+        let (_, bind) = arm();
+        let weights = [SkinWeights::single(1); 3];
+        unsafe {
+            let skeleton = mn_skeleton_new();
+            assert_eq!(mn_skeleton_add_joint(skeleton, -1, 0.0, 0.0, 0.0), 0);
+            assert_eq!(mn_skeleton_add_joint(skeleton, 0, 10.0, 0.0, 0.0), 1);
+            assert_eq!(mn_skeleton_add_joint(skeleton, 5, 0.0, 0.0, 0.0), -1);
+            mn_skeleton_set_pose(skeleton, 1, 0.0, 0.0, TAU / 4.0);
+
+            assert!(mn_skinned_mesh_new(core::ptr::null(), weights.as_ptr(), 1).is_null());
+            let skinned_mesh = mn_skinned_mesh_new(bind.tris.as_ptr(), weights.as_ptr(), 1);
+            assert_near(
+                (*skinned_mesh).pose(&*skeleton).tris[0].verts[0],
+                [10.0, 10.0, 0.0],
+            );
+
+            let renderer = mn_create();
+            mn_render_skinned(renderer, skinned_mesh, skeleton, 2.0, 0.0, 0.0, 0.0);
+            assert!((*renderer).buffer().drawn().is_some());
+
+            mn_destroy(renderer);
+            mn_skinned_mesh_free(skinned_mesh);
+            mn_skeleton_free(skeleton);
+        }
+    }
+}
@@ -0,0 +1,293 @@
+//! Stereoscopic rendering: the mesh is drawn once for each eye, seen from two cameras `interocular` pixels apart
+//! and `convergence` pixels in front of the screen, then the two views are combined for red/cyan glasses or laid
+//! out next to each other. Turn it on with [Renderer::set_stereo].
+//!
+//! Projection is straight on, so each eye's camera just shifts vertices sideways by their parallax: nothing on
+//! the screen plane (`z` of 0) moves, points in front of it (smaller `z`) split one way and points behind it the
+//! other.
+
+use crate::*;
+
+/// How the two eyes' views are put into the frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StereoMode {
+    /// Both views over the whole frame: the left eye in red and the right in cyan, for red/cyan glasses. Each eye
+    /// only writes its own channels, so where the two overlap comes out white.
+    Anaglyph,
+    /// Left eye in the left half, right eye in the right, each squashed to half width, for 3D TVs and headsets.
+    SideBySide,
+    /// Left eye in the top half, right eye in the bottom, each squashed to half height, for 3D TVs.
+    TopBottom,
+    /// Right eye in the left half, left eye in the right, both at half size so they keep their shape. Cross your
+    /// eyes until they overlap.
+    CrossEye,
+}
+
+impl StereoMode {
+    /// Picks a mode by number, for the C exports: 0 is anaglyph, then side-by-side, top-bottom and cross-eye.
+    pub fn from_id(id: u32) -> Option<StereoMode> {
+        Some(match id {
+            0 => StereoMode::Anaglyph,
+            1 => StereoMode::SideBySide,
+            2 => StereoMode::TopBottom,
+            3 => StereoMode::CrossEye,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eye {
+    Left,
+    Right,
+}
+
+/// Stereo camera settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stereo {
+    pub mode: StereoMode,
+    /// Distance between the eyes, in pixels. Wider pops out more.
+    pub interocular: f32,
+    /// Distance from the eyes to the screen plane, in pixels. Everything at this depth lines up in both views.
+    pub convergence: f32,
+}
+
+impl Stereo {
+    /// Settings that suit the demo meshes.
+    pub fn new(mode: StereoMode) -> Stereo {
+        Stereo {
+            mode,
+            interocular: 40.0,
+            convergence: 300.0,
+        }
+    }
+
+    /// The mesh as the eye sees it.
+    pub fn eye_view(&self, mesh: &Mesh, eye: Eye) -> Mesh {
+        let eye_x = match eye {
+            Eye::Left => -self.interocular / 2.0,
+            Eye::Right => self.interocular / 2.0,
+        };
+        let mut view = mesh.clone();
+        for vert in view.verts_mut() {
+            // where the ray from the eye through the vertex crosses the screen plane, sideways only. Vertices
+            // at or behind the eye are kept just in front of it
+            vert.x += eye_x * vert.z / (self.convergence + vert.z).max(1.0);
+        }
+        view
+    }
+
+    /// Draws the mesh into the frame buffer once for each eye, with edges `line_width` pixels wide.
+    pub fn render_to<F: PixelFormat>(
+        &self,
+        mesh: &Mesh,
+        frame_buffer: &mut FrameBuffer<F>,
+        line_width: f32,
+    ) {
+        let (width, height) = (frame_buffer.width() as u32, frame_buffer.height() as u32);
+        let (left, right) = (
+            self.eye_view(mesh, Eye::Left),
+            self.eye_view(mesh, Eye::Right),
+        );
+        match self.mode {
+            StereoMode::Anaglyph => {
+                let mut left_channels = Channels {
+                    frame_buffer: &mut *frame_buffer,
+                    mask: Pixel::rgb(255, 0, 0),
+                };
+                left.render_with_width(&mut left_channels, line_width);
+                let mut right_channels = Channels {
+                    frame_buffer,
+                    mask: Pixel::rgb(0, 255, 255),
+                };
+                right.render_with_width(&mut right_channels, line_width);
+            }
+            StereoMode::SideBySide | StereoMode::CrossEye => {
+                let (first, second, scale) = match self.mode {
+                    StereoMode::SideBySide => (left, right, (0.5, 1.0)),
+                    _ => (right, left, (0.5, 0.5)),
+                };
+                let half = width / 2;
+                for (view, x, width) in [(first, 0, half), (second, half, width - half)] {
+                    let rect = Rect {
+                        x,
+                        y: 0,
+                        width,
+                        height,
+                    };
+                    view.render_with_width(
+                        &mut Viewport::new(&mut *frame_buffer, rect, scale),
+                        line_width,
+                    );
+                }
+            }
+            StereoMode::TopBottom => {
+                let half = height / 2;
+                for (view, y, height) in [(left, 0, half), (right, half, height - half)] {
+                    let rect = Rect {
+                        x: 0,
+                        y,
+                        width,
+                        height,
+                    };
+                    let mut viewport = Viewport::new(&mut *frame_buffer, rect, (1.0, 0.5));
+                    view.render_with_width(&mut viewport, line_width);
+                }
+            }
+        }
+    }
+}
+
+/// Draws into part of another target, as if it were a target of its own. Lines are scaled about the viewport's
+/// centre and clipped to its edges, then handed on to the target's own [RenderTarget::draw_line].
+pub struct Viewport<'a, T: RenderTarget + ?Sized> {
+    target: &'a mut T,
+    rect: Rect,
+    /// Horizontal and vertical scale.
+    scale: (f32, f32),
+}
+
+impl<'a, T: RenderTarget + ?Sized> Viewport<'a, T> {
+    /// A viewport over `rect` of the target, which should lie inside it.
+    pub fn new(target: &'a mut T, rect: Rect, scale: (f32, f32)) -> Viewport<'a, T> {
+        Viewport {
+            target,
+            rect,
+            scale,
+        }
+    }
+}
+
+impl<T: RenderTarget + ?Sized> RenderTarget for Viewport<'_, T> {
+    fn size(&self) -> (usize, usize) {
+        (self.rect.width as usize, self.rect.height as usize)
+    }
+
+    fn plot_pixel(&mut self, x: i32, y: i32, pixel: &Pixel) {
+        if (0..self.rect.width as i32).contains(&x) && (0..self.rect.height as i32).contains(&y) {
+            self.target
+                .plot_pixel(x + self.rect.x as i32, y + self.rect.y as i32, pixel);
+        }
+    }
+
+    fn draw_line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, pixel: &Pixel) {
+        let (scale_x, scale_y) = self.scale;
+        let (width, height) = (self.rect.width as i32, self.rect.height as i32);
+        // the viewport's pixels, relative to its centre the way [centered] rounds them
+        let (left, top) = (-(width / 2) as f32, -(height / 2) as f32);
+        let (right, bottom) = (left + width as f32 - 1.0, top + height as f32 - 1.0);
+        let Some([x0, y0, x1, y1]) = clip(
+            [x0 * scale_x, y0 * scale_y, x1 * scale_x, y1 * scale_y],
+            [left, top, right, bottom],
+        ) else {
+            return;
+        };
+        // move the viewport's centre onto the target's
+        let (target_width, target_height) = self.target.size();
+        let dx = (self.rect.x as i32 + width / 2 - target_width as i32 / 2) as f32;
+        let dy = (self.rect.y as i32 + height / 2 - target_height as i32 / 2) as f32;
+        self.target
+            .draw_line(x0 + dx, y0 + dy, x1 + dx, y1 + dy, pixel);
+    }
+
+    /// Scaled like [Viewport::draw_line], and the width by the larger of the scales, then filled in clipped to the
+    /// viewport.
+    fn draw_thick_line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, width: f32, pixel: &Pixel) {
+        let (scale_x, scale_y) = self.scale;
+        let width = width * scale_x.max(scale_y);
+        if width <= 1.0 {
+            self.draw_line(x0, y0, x1, y1, pixel);
+        } else {
+            let line = [x0 * scale_x, y0 * scale_y, x1 * scale_x, y1 * scale_y];
+            primitives::centred_thick_line(self, line, width, pixel);
+        }
+    }
+
+    fn fill_span(&mut self, x: i32, y: i32, len: usize, pixel: &Pixel) {
+        let (start, end) = (x.max(0), (x + len as i32).min(self.rect.width as i32));
+        if (0..self.rect.height as i32).contains(&y) && start < end {
+            self.target.fill_span(
+                start + self.rect.x as i32,
+                y + self.rect.y as i32,
+                (end - start) as usize,
+                pixel,
+            );
+        }
+    }
+}
+
+/// Clips the line to the box, `[left, top, right, bottom]`, with the Liang–Barsky algorithm. [None] if none of
+/// it is inside.
+fn clip([x0, y0, x1, y1]: [f32; 4], [left, top, right, bottom]: [f32; 4]) -> Option<[f32; 4]> {
+    let (dx, dy) = (x1 - x0, y1 - y0);
+    let (mut enter, mut leave) = (0.0f32, 1.0f32);
+    for (p, q) in [
+        (-dx, x0 - left),
+        (dx, right - x0),
+        (-dy, y0 - top),
+        (dy, bottom - y0),
+    ] {
+        if p == 0.0 {
+            // parallel to this edge
+            if q < 0.0 {
+                return None;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                enter = enter.max(t);
+            } else {
+                leave = leave.min(t);
+            }
+        }
+    }
+    (enter <= leave).then_some([
+        x0 + enter * dx,
+        y0 + enter * dy,
+        x0 + leave * dx,
+        y0 + leave * dy,
+    ])
+}
+
+/// Draws into some channels of a frame buffer only, leaving the rest as they were. Channels set in `mask` come
+/// from the line colour.
+struct Channels<'a, F: PixelFormat> {
+    frame_buffer: &'a mut FrameBuffer<F>,
+    mask: Pixel,
+}
+
+impl<F: PixelFormat> RenderTarget for Channels<'_, F> {
+    fn size(&self) -> (usize, usize) {
+        self.frame_buffer.size()
+    }
+
+    fn plot_pixel(&mut self, x: i32, y: i32, pixel: &Pixel) {
+        if x < 0 || y < 0 {
+            return;
+        }
+        let Some(under) = self.frame_buffer.pixel(x as usize, y as usize) else {
+            return;
+        };
+        let pick = |mask: u8, line: u8, under: u8| (line & mask) | (under & !mask);
+        let merged = Pixel {
+            r: pick(self.mask.r, pixel.r, under.r),
+            g: pick(self.mask.g, pixel.g, under.g),
+            b: pick(self.mask.b, pixel.b, under.b),
+            a: under.a,
+        };
+        self.frame_buffer.plot_pixel(x, y, &merged);
+    }
+}
+
+/// Turns stereo rendering on, in one of the modes picked by [StereoMode::from_id], with the eyes `interocular`
+/// pixels apart. Any other id turns it off.
+///
+/// # Safety
+/// `renderer` must come from [mn_create].
+#[no_mangle]
+pub unsafe extern "C" fn mn_set_stereo(renderer: *mut Renderer, id: u32, interocular: f32) {
+    (*renderer).set_stereo(StereoMode::from_id(id).map(|mode| Stereo {
+        interocular,
+        ..Stereo::new(mode)
+    }));
+}
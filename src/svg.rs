@@ -0,0 +1,220 @@
+//! SVG export of wireframes. Instead of being rasterized, the same projected lines `Tri::render` would draw are
+//! kept as vectors, so the picture stays crisp at any size. Hidden-line removal can be turned on to leave out
+//! the parts of edges covered by nearer triangles; like everywhere else, smaller z is nearer the viewer.
+
+#[cfg(target_arch = "powerpc")]
+use alloc::string::String;
+use core::fmt::Write;
+
+use crate::*;
+
+/// Lines closer to a triangle than this, in depth or across its edges, don't count as hidden by it. It keeps
+/// edges from being hidden by the triangles they belong to.
+const EPSILON: f32 = 1e-3;
+
+/// Collects lines and writes them out as an SVG image. Draw into it like any other [RenderTarget], or with
+/// [Svg::draw_mesh] for hidden-line removal.
+#[derive(Debug, Clone)]
+pub struct Svg {
+    width: usize,
+    height: usize,
+    /// Lines in pixel coordinates, `[x0, y0, x1, y1]`.
+    lines: Vec<[f32; 4]>,
+    pub stroke: Pixel,
+    pub stroke_width: f32,
+    /// Fill behind the lines, if any.
+    pub background: Option<Pixel>,
+    /// Leave out the parts of edges hidden behind other triangles when drawing meshes.
+    pub hidden_line_removal: bool,
+}
+
+impl Svg {
+    /// Creates an empty `width` by `height` picture, with thin white lines and no background.
+    pub fn new(width: usize, height: usize) -> Svg {
+        Svg {
+            width,
+            height,
+            lines: Vec::new(),
+            stroke: Pixel::WHITE,
+            stroke_width: 1.0,
+            background: None,
+            hidden_line_removal: false,
+        }
+    }
+
+    /// Adds the mesh's edges. Edges shared by two triangles are only added once.
+    pub fn draw_mesh(&mut self, mesh: &Mesh) {
+        let mut edges: Vec<[Vertex; 2]> = mesh
+            .tris
+            .iter()
+            .flat_map(|tri| {
+                let [a, b, c] = tri.verts;
+                [[a, b], [b, c], [c, a]]
+            })
+            .collect();
+        // the same edge can run either way, so put the ends in order before looking for repeats
+        let ordered = |[a, b]: [Vertex; 2]| {
+            let key = |vert: Vertex| [vert.x, vert.y, vert.z];
+            if key(a) <= key(b) {
+                [key(a), key(b)]
+            } else {
+                [key(b), key(a)]
+            }
+        };
+        edges.sort_by(|&edge1, &edge2| {
+            ordered(edge1)
+                .partial_cmp(&ordered(edge2))
+                .unwrap_or(core::cmp::Ordering::Equal)
+        });
+        edges.dedup_by(|edge1, edge2| ordered(*edge1) == ordered(*edge2));
+
+        let stroke = self.stroke;
+        for [from, to] in edges {
+            // the same projection as Tri::edges
+            let edge = Tri {
+                verts: [from, to, from],
+            }
+            .edges()[0];
+            if !self.hidden_line_removal {
+                self.draw_line(edge[0], edge[1], edge[2], edge[3], &stroke);
+                continue;
+            }
+            let mut hidden: Vec<[f32; 2]> = mesh
+                .tris
+                .iter()
+                .filter_map(|tri| hidden_by(from, to, tri))
+                .collect();
+            hidden.sort_by(|span1, span2| {
+                span1[0]
+                    .partial_cmp(&span2[0])
+                    .unwrap_or(core::cmp::Ordering::Equal)
+            });
+            // draw whatever the hidden spans leave uncovered
+            let point = |t: f32| {
+                [
+                    edge[0] + (edge[2] - edge[0]) * t,
+                    edge[1] + (edge[3] - edge[1]) * t,
+                ]
+            };
+            let mut visible_from = 0.0;
+            for [start, end] in hidden.into_iter().chain([[1.0, 1.0]]) {
+                if start > visible_from + EPSILON {
+                    let ([x0, y0], [x1, y1]) = (point(visible_from), point(start));
+                    self.draw_line(x0, y0, x1, y1, &stroke);
+                }
+                visible_from = visible_from.max(end);
+            }
+        }
+    }
+
+    /// Writes out the SVG file.
+    pub fn finish(&self) -> String {
+        let mut out = String::new();
+        let hex = |pixel: Pixel| (pixel.r as u32) << 16 | (pixel.g as u32) << 8 | pixel.b as u32;
+        // writing to a String can't fail
+        let _ = writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+            self.width, self.height
+        );
+        if let Some(background) = self.background {
+            let _ = writeln!(
+                out,
+                "<rect width=\"100%\" height=\"100%\" fill=\"#{:06x}\"/>",
+                hex(background)
+            );
+        }
+        let _ = write!(
+            out,
+            "<path fill=\"none\" stroke=\"#{:06x}\" stroke-width=\"{}\" stroke-linecap=\"round\" d=\"",
+            hex(self.stroke),
+            self.stroke_width
+        );
+        for (idx_line, [x0, y0, x1, y1]) in self.lines.iter().enumerate() {
+            let separator = if idx_line == 0 { "" } else { " " };
+            let _ = write!(out, "{separator}M{x0:.2} {y0:.2}L{x1:.2} {y1:.2}");
+        }
+        out.push_str("\"/>\n</svg>\n");
+        out
+    }
+}
+
+/// Lines are kept as vectors, in the SVG's stroke colour rather than the one they're drawn in. Pixels and spans
+/// become dots and short lines.
+impl RenderTarget for Svg {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn plot_pixel(&mut self, x: i32, y: i32, _pixel: &Pixel) {
+        self.lines.push([x as f32, y as f32, x as f32, y as f32]);
+    }
+
+    fn draw_line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, _pixel: &Pixel) {
+        let (half_width, half_height) = ((self.width / 2) as f32, (self.height / 2) as f32);
+        self.lines.push([
+            x0 + half_width,
+            y0 + half_height,
+            x1 + half_width,
+            y1 + half_height,
+        ]);
+    }
+
+    /// Kept as a line like any other, as wide as [Svg::stroke_width] rather than `width`.
+    fn draw_thick_line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, _width: f32, pixel: &Pixel) {
+        self.draw_line(x0, y0, x1, y1, pixel);
+    }
+
+    fn fill_span(&mut self, x: i32, y: i32, len: usize, _pixel: &Pixel) {
+        let end = x as f32 + len.saturating_sub(1) as f32;
+        self.lines.push([x as f32, y as f32, end, y as f32]);
+    }
+}
+
+/// The span of the edge from `from` to `to`, as fractions of the way along it, that's behind the triangle on
+/// screen. [None] if none of it is.
+fn hidden_by(from: Vertex, to: Vertex, tri: &Tri) -> Option<[f32; 2]> {
+    let [a, b, c] = tri.verts;
+    let area = (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+    // triangles seen edge on can't cover anything
+    if area.abs() < EPSILON {
+        return None;
+    }
+    let (mut start, mut end) = (0.0f32, 1.0f32);
+    // keep the part of the edge where `value` goes from `at_from` to `at_to` and stays above EPSILON
+    let mut clip = |at_from: f32, at_to: f32| {
+        let slope = at_to - at_from;
+        if slope.abs() < f32::EPSILON {
+            if at_from <= EPSILON {
+                end = -1.0;
+            }
+        } else {
+            let cross = (EPSILON - at_from) / slope;
+            if slope > 0.0 {
+                start = start.max(cross);
+            } else {
+                end = end.min(cross);
+            }
+        }
+    };
+
+    // inside all three of the triangle's edges on screen
+    for (vert1, vert2) in [(a, b), (b, c), (c, a)] {
+        let side = |point: Vertex| {
+            area.signum()
+                * ((vert2.x - vert1.x) * (point.y - vert1.y)
+                    - (vert2.y - vert1.y) * (point.x - vert1.x))
+        };
+        clip(side(from), side(to));
+    }
+    // and farther away than the triangle's plane
+    let depth_on_plane = |point: Vertex| {
+        // barycentric weights of the point on screen
+        let weight_b = ((point.x - a.x) * (c.y - a.y) - (point.y - a.y) * (c.x - a.x)) / area;
+        let weight_c = ((b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x)) / area;
+        a.z + (b.z - a.z) * weight_b + (c.z - a.z) * weight_c
+    };
+    clip(from.z - depth_on_plane(from), to.z - depth_on_plane(to));
+
+    (start < end).then_some([start, end])
+}
@@ -0,0 +1,150 @@
+//! Render targets: anything meshes can be drawn into with [Mesh::render_to]. [FrameBuffer]s rasterize,
+//! [Svg](crate::svg::Svg)s keep the lines as vectors and [SegmentRecorder]s just write them down. Implement
+//! [RenderTarget] to draw straight into your own surfaces; only [RenderTarget::size] and
+//! [RenderTarget::plot_pixel] are required.
+
+use crate::*;
+
+/// Something the renderer can draw into.
+pub trait RenderTarget {
+    /// Width and height, in pixels.
+    fn size(&self) -> (usize, usize);
+
+    /// Plots a single pixel. Pixels outside the target are ignored.
+    fn plot_pixel(&mut self, x: i32, y: i32, pixel: &Pixel);
+
+    /// Draws a line. Coordinates are relative to the centre of the target, the way meshes are projected. By
+    /// default, the line is rasterized with Bresenham's algorithm.
+    fn draw_line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, pixel: &Pixel) {
+        let [x0, y0, x1, y1] = centered(self.size(), [x0, y0, x1, y1]);
+        bresenham(x0, y0, x1, y1, |x, y| self.plot_pixel(x, y, pixel));
+    }
+
+    /// Draws a line `width` pixels wide, with rounded ends so lines meeting at a point join up without gaps or
+    /// notches. Coordinates are relative to the centre of the target, like [RenderTarget::draw_line], which lines
+    /// a pixel wide or less are drawn with. By default, thick lines are filled in with
+    /// [primitives::thick_line].
+    fn draw_thick_line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, width: f32, pixel: &Pixel) {
+        if width <= 1.0 {
+            self.draw_line(x0, y0, x1, y1, pixel);
+        } else {
+            primitives::centred_thick_line(self, [x0, y0, x1, y1], width, pixel);
+        }
+    }
+
+    /// Fills `len` pixels of row `y`, starting from column `x` and going right. Pixels outside the target are
+    /// ignored.
+    fn fill_span(&mut self, x: i32, y: i32, len: usize, pixel: &Pixel) {
+        for idx in 0..len as i32 {
+            self.plot_pixel(x + idx, y, pixel);
+        }
+    }
+}
+
+impl<F: PixelFormat> RenderTarget for FrameBuffer<F> {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn plot_pixel(&mut self, x: i32, y: i32, pixel: &Pixel) {
+        if (0..self.width as i32).contains(&x) && (0..self.height as i32).contains(&y) {
+            let stride = self.stride();
+            F::encode(pixel, &mut self.bytes[y as usize * stride..], x as usize);
+            self.mark_drawn(Rect {
+                x: x as u32,
+                y: y as u32,
+                width: 1,
+                height: 1,
+            });
+        }
+    }
+
+    fn fill_span(&mut self, x: i32, y: i32, len: usize, pixel: &Pixel) {
+        let (start, end) = (x.max(0), (x + len as i32).min(self.width as i32));
+        if !(0..self.height as i32).contains(&y) || start >= end {
+            return;
+        }
+        let stride = self.stride();
+        let row = &mut self.bytes[y as usize * stride..(y as usize + 1) * stride];
+        for x in start as usize..end as usize {
+            F::encode(pixel, row, x);
+        }
+        self.mark_drawn(Rect {
+            x: start as u32,
+            y: y as u32,
+            width: (end - start) as u32,
+            height: 1,
+        });
+    }
+}
+
+/// Draws nothing, and just writes down every line it's given. Pixels and spans are ignored. Handy for checking
+/// what the renderer would draw without looking at pixels.
+#[derive(Debug, Clone, Default)]
+pub struct SegmentRecorder {
+    pub width: usize,
+    pub height: usize,
+    /// Every line drawn, `[x0, y0, x1, y1]` relative to the centre, with its colour.
+    pub segments: Vec<([f32; 4], Pixel)>,
+}
+
+impl SegmentRecorder {
+    pub fn new(width: usize, height: usize) -> SegmentRecorder {
+        SegmentRecorder {
+            width,
+            height,
+            segments: Vec::new(),
+        }
+    }
+}
+
+impl RenderTarget for SegmentRecorder {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn plot_pixel(&mut self, _x: i32, _y: i32, _pixel: &Pixel) {}
+
+    fn draw_line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, pixel: &Pixel) {
+        self.segments.push(([x0, y0, x1, y1], *pixel));
+    }
+
+    /// Written down like any other line; the width isn't kept.
+    fn draw_thick_line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, _width: f32, pixel: &Pixel) {
+        self.draw_line(x0, y0, x1, y1, pixel);
+    }
+
+    fn fill_span(&mut self, _x: i32, _y: i32, _len: usize, _pixel: &Pixel) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts lines, and fails if anything asks it for a thick one.
+    struct LineCounter(usize);
+
+    impl RenderTarget for LineCounter {
+        fn size(&self) -> (usize, usize) {
+            (100, 100)
+        }
+
+        fn plot_pixel(&mut self, _x: i32, _y: i32, _pixel: &Pixel) {}
+
+        fn draw_line(&mut self, _x0: f32, _y0: f32, _x1: f32, _y1: f32, _pixel: &Pixel) {
+            self.0 += 1;
+        }
+
+        fn draw_thick_line(&mut self, _: f32, _: f32, _: f32, _: f32, _: f32, _: &Pixel) {
+            panic!("render_to draws through draw_line");
+        }
+    }
+
+    #[test]
+    fn render_to_draws_lines() {
+        let cube = Mesh::cube(Vec3::from([-10.0; 3]), Vec3::from([10.0; 3]));
+        let mut counter = LineCounter(0);
+        cube.render_to(&mut counter);
+        assert_eq!(counter.0, cube.tris.len() * 3);
+    }
+}
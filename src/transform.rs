@@ -0,0 +1,199 @@
+//! Batch vertex transforms. Vertices are gathered into structure-of-arrays lanes and multiplied by the matrix
+//! with SIMD where the target has it: SSE, or AVX when the CPU supports it, on x86_64, and simd128 on wasm32 when
+//! built with `-C target-feature=+simd128`. Everything else, like the Wii's PowerPC, uses [transform_scalar].
+//! Every path does the same multiplications and additions in the same order as `Mat3x3 * Vec3`, so they all
+//! give exactly the same results.
+
+use crate::*;
+
+/// Multiplies every vertex by the matrix, using the fastest path the CPU supports.
+pub fn transform(mat: &Mat3x3, verts: &mut [Vertex]) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        let verts = if std::is_x86_feature_detected!("avx") {
+            unsafe { x86_64::transform_avx(mat, verts) }
+        } else {
+            x86_64::transform_sse(mat, verts)
+        };
+        transform_scalar(mat, verts);
+    }
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    {
+        let verts = wasm32::transform_simd128(mat, verts);
+        transform_scalar(mat, verts);
+    }
+    #[cfg(not(any(
+        target_arch = "x86_64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    )))]
+    transform_scalar(mat, verts);
+}
+
+/// Multiplies every vertex by the matrix, one at a time.
+pub fn transform_scalar(mat: &Mat3x3, verts: &mut [Vertex]) {
+    for vert in verts {
+        *vert = *mat * *vert;
+    }
+}
+
+/// Calls `kernel` on the vertices, `LANES` at a time, gathered into one array per axis. The transformed lanes
+/// are scattered back into the vertices. Returns the leftover vertices that don't fill a whole set of lanes.
+#[cfg_attr(
+    not(any(
+        target_arch = "x86_64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    )),
+    allow(dead_code)
+)]
+#[inline(always)]
+fn for_each_lanes<const LANES: usize>(
+    verts: &mut [Vertex],
+    mut kernel: impl FnMut(&mut [f32; LANES], &mut [f32; LANES], &mut [f32; LANES]),
+) -> &mut [Vertex] {
+    let mut chunks = verts.chunks_exact_mut(LANES);
+    for chunk in &mut chunks {
+        let mut xs = [0.0; LANES];
+        let mut ys = [0.0; LANES];
+        let mut zs = [0.0; LANES];
+        for (idx_lane, vert) in chunk.iter().enumerate() {
+            xs[idx_lane] = vert.x;
+            ys[idx_lane] = vert.y;
+            zs[idx_lane] = vert.z;
+        }
+        kernel(&mut xs, &mut ys, &mut zs);
+        for (idx_lane, vert) in chunk.iter_mut().enumerate() {
+            *vert = Vec3::from([xs[idx_lane], ys[idx_lane], zs[idx_lane]]);
+        }
+    }
+    chunks.into_remainder()
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64 {
+    use super::*;
+    use core::arch::x86_64::*;
+
+    /// # Safety
+    /// The CPU must support AVX.
+    #[target_feature(enable = "avx")]
+    pub(super) unsafe fn transform_avx<'a>(
+        mat: &Mat3x3,
+        verts: &'a mut [Vertex],
+    ) -> &'a mut [Vertex] {
+        let m = mat.mat.map(|row| row.map(|cell| _mm256_set1_ps(cell)));
+        for_each_lanes::<8>(verts, |xs, ys, zs| {
+            let (x, y, z) = (
+                _mm256_loadu_ps(xs.as_ptr()),
+                _mm256_loadu_ps(ys.as_ptr()),
+                _mm256_loadu_ps(zs.as_ptr()),
+            );
+            let row = |row: [__m256; 3]| {
+                _mm256_add_ps(
+                    _mm256_add_ps(_mm256_mul_ps(x, row[0]), _mm256_mul_ps(y, row[1])),
+                    _mm256_mul_ps(z, row[2]),
+                )
+            };
+            _mm256_storeu_ps(xs.as_mut_ptr(), row(m[0]));
+            _mm256_storeu_ps(ys.as_mut_ptr(), row(m[1]));
+            _mm256_storeu_ps(zs.as_mut_ptr(), row(m[2]));
+        })
+    }
+
+    /// SSE is part of x86_64, so this one needs no detection.
+    pub(super) fn transform_sse<'a>(mat: &Mat3x3, verts: &'a mut [Vertex]) -> &'a mut [Vertex] {
+        let m = mat
+            .mat
+            .map(|row| row.map(|cell| unsafe { _mm_set1_ps(cell) }));
+        for_each_lanes::<4>(verts, |xs, ys, zs| unsafe {
+            let (x, y, z) = (
+                _mm_loadu_ps(xs.as_ptr()),
+                _mm_loadu_ps(ys.as_ptr()),
+                _mm_loadu_ps(zs.as_ptr()),
+            );
+            let row = |row: [__m128; 3]| {
+                _mm_add_ps(
+                    _mm_add_ps(_mm_mul_ps(x, row[0]), _mm_mul_ps(y, row[1])),
+                    _mm_mul_ps(z, row[2]),
+                )
+            };
+            _mm_storeu_ps(xs.as_mut_ptr(), row(m[0]));
+            _mm_storeu_ps(ys.as_mut_ptr(), row(m[1]));
+            _mm_storeu_ps(zs.as_mut_ptr(), row(m[2]));
+        })
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod wasm32 {
+    use super::*;
+    use core::arch::wasm32::*;
+
+    pub(super) fn transform_simd128<'a>(mat: &Mat3x3, verts: &'a mut [Vertex]) -> &'a mut [Vertex] {
+        let m = mat.mat.map(|row| row.map(|cell| f32x4_splat(cell)));
+        for_each_lanes::<4>(verts, |xs, ys, zs| {
+            let (x, y, z) = (
+                f32x4(xs[0], xs[1], xs[2], xs[3]),
+                f32x4(ys[0], ys[1], ys[2], ys[3]),
+                f32x4(zs[0], zs[1], zs[2], zs[3]),
+            );
+            let row = |row: [v128; 3]| {
+                f32x4_add(
+                    f32x4_add(f32x4_mul(x, row[0]), f32x4_mul(y, row[1])),
+                    f32x4_mul(z, row[2]),
+                )
+            };
+            for (lanes, result) in [(xs, row(m[0])), (ys, row(m[1])), (zs, row(m[2]))] {
+                *lanes = [
+                    f32x4_extract_lane::<0>(result),
+                    f32x4_extract_lane::<1>(result),
+                    f32x4_extract_lane::<2>(result),
+                    f32x4_extract_lane::<3>(result),
+                ];
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Vertices with no two coordinates alike, so every lane and axis gets its own value.
+    fn verts(count: usize) -> Vec<Vertex> {
+        (0..count)
+            .map(|idx| {
+                let idx = idx as f32;
+                Vec3::from([idx * 1.7 - 13.1, 5.3 - idx * 0.9, idx * idx * 0.37 + 0.01])
+            })
+            .collect()
+    }
+
+    fn bits(verts: &[Vertex]) -> Vec<[u32; 3]> {
+        verts
+            .iter()
+            .map(|vert| [vert.x.to_bits(), vert.y.to_bits(), vert.z.to_bits()])
+            .collect()
+    }
+
+    #[test]
+    fn transform_matches_scalar() {
+        for (name, axis) in [("x", Axis::X), ("y", Axis::Y), ("z", Axis::Z)] {
+            let mat = Mat3x3::rot(0.7, axis);
+            for count in 0..=17 {
+                let mut expected = verts(count);
+                transform_scalar(&mat, &mut expected);
+                let mut simd = verts(count);
+                transform(&mat, &mut simd);
+                assert_eq!(bits(&simd), bits(&expected), "{count} verts about {name}");
+                #[cfg(target_arch = "x86_64")]
+                {
+                    // transform picks AVX where it can, so check the SSE path as well
+                    let mut sse = verts(count);
+                    let rest = x86_64::transform_sse(&mat, &mut sse);
+                    transform_scalar(&mat, rest);
+                    assert_eq!(bits(&sse), bits(&expected), "{count} verts about {name}");
+                }
+            }
+        }
+    }
+}
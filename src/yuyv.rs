@@ -0,0 +1,408 @@
+//! YUYV (YUV 4:2:2) output, the format the Wii's external frame buffer and many video devices take. Each pair of
+//! pixels is stored in four bytes: the first pixel's brightness, the pair's shared blue difference, the second
+//! pixel's brightness and the pair's shared red difference. Colours are studio range: brightness runs from 16 to
+//! 235 and the differences from 16 to 240.
+//!
+//! The shared colour of each pair is filtered over the pixels either side of it, `[1, 2, 1] / 4`, so sharp
+//! coloured edges don't fringe. For example:
+//! ```
+//! use meenle_noonle::yuyv::{encode_row, rgb_to_yuv, Matrix};
+//! use meenle_noonle::Pixel;
+//!
+//! assert_eq!(rgb_to_yuv(&Pixel::rgb(255, 255, 255), Matrix::Bt601), [235, 128, 128]);
+//! assert_eq!(rgb_to_yuv(&Pixel::rgb(255, 0, 0), Matrix::Bt601), [82, 90, 240]);
+//! assert_eq!(rgb_to_yuv(&Pixel::rgb(255, 0, 0), Matrix::Bt709), [63, 102, 240]);
+//!
+//! let red = Pixel::rgb(255, 0, 0);
+//! let black = Pixel::rgb(0, 0, 0);
+//! let mut yuyv = [0; 8];
+//! encode_row(&[black, black, red, red], &mut yuyv, Matrix::Bt601);
+//! // the second pair's colour picks up a quarter of the black before it
+//! assert_eq!(yuyv, [16, 128, 16, 128, 82, 100, 82, 212]);
+//! ```
+
+use crate::scale::{Filter, ScaleMode, Scaler};
+use crate::*;
+
+/// How RGB is turned into brightness and colour differences.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Matrix {
+    /// Standard definition video, and what the Wii expects.
+    #[default]
+    Bt601,
+    /// High definition video.
+    Bt709,
+}
+
+impl Matrix {
+    /// Fixed point, 8 fractional bits, rows for Y, U and V. The U and V rows add up to 0, so greys have no colour.
+    fn forward(self) -> [[i32; 3]; 3] {
+        match self {
+            Matrix::Bt601 => [[66, 129, 25], [-38, -74, 112], [112, -94, -18]],
+            Matrix::Bt709 => [[47, 157, 16], [-26, -86, 112], [112, -102, -10]],
+        }
+    }
+
+    /// Fixed point, 8 fractional bits: how much of U and V goes into red, green and blue.
+    fn inverse(self) -> [[i32; 2]; 3] {
+        match self {
+            Matrix::Bt601 => [[0, 409], [-100, -208], [516, 0]],
+            Matrix::Bt709 => [[0, 459], [-55, -136], [541, 0]],
+        }
+    }
+}
+
+/// Brightness and the two colour differences of a pixel, `[y, u, v]`. Alpha is ignored.
+pub fn rgb_to_yuv(pixel: &Pixel, matrix: Matrix) -> [u8; 3] {
+    let [y, u, v] = yuv(pixel, matrix);
+    [y as u8, u as u8, v as u8]
+}
+
+fn yuv(pixel: &Pixel, matrix: Matrix) -> [i32; 3] {
+    let rgb = [pixel.r as i32, pixel.g as i32, pixel.b as i32];
+    let [row_y, row_u, row_v] = matrix.forward();
+    let dot = |row: [i32; 3]| (row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2] + 128) >> 8;
+    [dot(row_y) + 16, dot(row_u) + 128, dot(row_v) + 128]
+}
+
+/// The opaque pixel with the given brightness and colour differences.
+pub fn yuv_to_rgb(yuv: [u8; 3], matrix: Matrix) -> Pixel {
+    let luma = 298 * (yuv[0] as i32 - 16);
+    let (u, v) = (yuv[1] as i32 - 128, yuv[2] as i32 - 128);
+    let [red, green, blue] = matrix
+        .inverse()
+        .map(|[of_u, of_v]| ((luma + of_u * u + of_v * v + 128) >> 8).clamp(0, 255) as u8);
+    Pixel::rgb(red, green, blue)
+}
+
+/// Converts a row of pixels into YUYV, four bytes for every two pixels. An odd last pixel is paired with itself.
+/// `yuyv` must hold `pixels.len().div_ceil(2) * 4` bytes.
+pub fn encode_row(pixels: &[Pixel], yuyv: &mut [u8], matrix: Matrix) {
+    let Some(last) = pixels.len().checked_sub(1) else {
+        return;
+    };
+    let at = |x: usize| yuv(&pixels[x.min(last)], matrix);
+    for (idx_pair, bytes) in yuyv
+        .chunks_exact_mut(4)
+        .take(pixels.len().div_ceil(2))
+        .enumerate()
+    {
+        let x = idx_pair * 2;
+        let (before, here, after) = (at(x.saturating_sub(1)), at(x), at(x + 1));
+        // chroma sits on the first pixel of the pair, filtered with its neighbours
+        let filter = |idx: usize| ((before[idx] + 2 * here[idx] + after[idx] + 2) >> 2) as u8;
+        bytes.copy_from_slice(&[here[0] as u8, filter(1), after[0] as u8, filter(2)]);
+    }
+}
+
+/// Converts a whole frame buffer into YUYV, row by row. Odd widths get a column repeated at the end.
+pub fn encode(frame_buffer: &FrameBuffer, matrix: Matrix) -> Vec<u8> {
+    let (width, height) = (frame_buffer.width(), frame_buffer.height());
+    let stride = width.div_ceil(2) * 4;
+    let mut yuyv = vec![0; stride * height];
+    for (row, bytes) in frame_buffer
+        .pixels()
+        .chunks_exact(width.max(1))
+        .zip(yuyv.chunks_exact_mut(stride.max(1)))
+    {
+        encode_row(row, bytes, matrix);
+    }
+    yuyv
+}
+
+/// Plots a single pixel into YUYV bytes: its own brightness, and its colour for the whole pair, since there are
+/// no neighbours to filter with. Whole rows, like backgrounds, are filtered.
+fn encode_pixel(pixel: &Pixel, row: &mut [u8], x: usize, matrix: Matrix) {
+    let [y, u, v] = rgb_to_yuv(pixel, matrix);
+    let pair = x / 2 * 4;
+    row[pair + x % 2 * 2] = y;
+    row[pair + 1] = u;
+    row[pair + 3] = v;
+}
+
+fn decode_pixel(row: &[u8], x: usize, matrix: Matrix) -> Pixel {
+    let pair = x / 2 * 4;
+    yuv_to_rgb(
+        [row[pair + x % 2 * 2], row[pair + 1], row[pair + 3]],
+        matrix,
+    )
+}
+
+/// YUYV with BT.601 colours, for rendering straight into video memory with [Renderer::with_format]. Rows are
+/// padded to a whole number of pairs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Yuyv;
+
+impl PixelFormat for Yuyv {
+    const BITS_PER_PIXEL: usize = 16;
+
+    fn encode(pixel: &Pixel, row: &mut [u8], x: usize) {
+        encode_pixel(pixel, row, x, Matrix::Bt601);
+    }
+
+    fn decode(row: &[u8], x: usize) -> Pixel {
+        decode_pixel(row, x, Matrix::Bt601)
+    }
+
+    fn encode_row(pixels: &[Pixel], row: &mut [u8]) {
+        encode_row(pixels, row, Matrix::Bt601);
+    }
+
+    fn stride(width: usize) -> usize {
+        width.div_ceil(2) * 4
+    }
+}
+
+/// [Yuyv] with BT.709 colours.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Yuyv709;
+
+impl PixelFormat for Yuyv709 {
+    const BITS_PER_PIXEL: usize = 16;
+
+    fn encode(pixel: &Pixel, row: &mut [u8], x: usize) {
+        encode_pixel(pixel, row, x, Matrix::Bt709);
+    }
+
+    fn decode(row: &[u8], x: usize) -> Pixel {
+        decode_pixel(row, x, Matrix::Bt709)
+    }
+
+    fn encode_row(pixels: &[Pixel], row: &mut [u8]) {
+        encode_row(pixels, row, Matrix::Bt709);
+    }
+
+    fn stride(width: usize) -> usize {
+        width.div_ceil(2) * 4
+    }
+}
+
+/// Converts two pixels, given as `0xRRGGBB`, into one BT.601 YUYV pair, `0xYYUUYYVV`. The pair's colour is the
+/// average of the two.
+#[no_mangle]
+pub extern "C" fn mn_rgb_to_yuyv(color1: u32, color2: u32) -> u32 {
+    let [y1, u1, v1] = yuv(&Pixel::from_hex(color1), Matrix::Bt601);
+    let [y2, u2, v2] = yuv(&Pixel::from_hex(color2), Matrix::Bt601);
+    u32::from_be_bytes([
+        y1 as u8,
+        ((u1 + u2 + 1) / 2) as u8,
+        y2 as u8,
+        ((v1 + v2 + 1) / 2) as u8,
+    ])
+}
+
+/// Draws rows `row_start..row_end` of the renderer's frame buffer into a `xfb_width` by `xfb_height` YUYV
+/// frame buffer, like the Wii's XFB. The picture is scaled to fit with [ScaleMode::Fit] and bilinear filtering, and
+/// the bars around it are `bar_color` (`0xRRGGBB`). Only the XFB rows those frame buffer rows end up in are
+/// redrawn.
+///
+/// # Safety
+/// `renderer` must come from [mn_create], `xfb_width` must be even and `xfb` must point to
+/// `xfb_width * xfb_height * 2` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn mn_draw_into_xfb(
+    renderer: *const Renderer,
+    xfb: *mut u8,
+    xfb_width: u32,
+    xfb_height: u32,
+    bar_color: u32,
+    row_start: u32,
+    row_end: u32,
+) {
+    let frame_buffer = (*renderer).buffer();
+    let (xfb_width, xfb_height) = (xfb_width as usize, xfb_height as usize);
+    let xfb = core::slice::from_raw_parts_mut(xfb, xfb_width * xfb_height * 2);
+    let mut scaler = Scaler::new(
+        (frame_buffer.width(), frame_buffer.height()),
+        (xfb_width, xfb_height),
+        ScaleMode::Fit,
+        Filter::Bilinear,
+    );
+    scaler.bars = Pixel::from_hex(bar_color);
+    let rows = scaler.destination_rows(row_start as usize..row_end as usize);
+    let stride = xfb_width * 2;
+    scaler.scale_rows(frame_buffer, rows, |idx_row, row| {
+        encode_row(
+            row,
+            &mut xfb[idx_row * stride..(idx_row + 1) * stride],
+            Matrix::Bt601,
+        );
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::ops::Range;
+
+    const COLOURS: [u32; 10] = [
+        0x000000, 0xffffff, 0x808080, 0xff0000, 0x00ff00, 0x0000ff, 0xffff00, 0x00ffff, 0xff00ff,
+        0x3a6073,
+    ];
+
+    /// Studio range YUV straight from the standards' luma weights, in floating point.
+    fn reference(color: u32, matrix: Matrix) -> [f64; 3] {
+        let (kr, kb) = match matrix {
+            Matrix::Bt601 => (0.299, 0.114),
+            Matrix::Bt709 => (0.2126, 0.0722),
+        };
+        let [_, r, g, b] = color.to_be_bytes().map(|channel| channel as f64 / 255.0);
+        let luma = kr * r + (1.0 - kr - kb) * g + kb * b;
+        [
+            16.0 + 219.0 * luma,
+            128.0 + 224.0 * (b - luma) / (2.0 * (1.0 - kb)),
+            128.0 + 224.0 * (r - luma) / (2.0 * (1.0 - kr)),
+        ]
+    }
+
+    #[test]
+    fn matches_reference() {
+        for matrix in [Matrix::Bt601, Matrix::Bt709] {
+            for color in COLOURS {
+                let yuv = rgb_to_yuv(&Pixel::from_hex(color), matrix);
+                for (got, expected) in yuv.iter().zip(reference(color, matrix)) {
+                    assert!(
+                        (*got as f64 - expected).abs() <= 1.0,
+                        "{color:06x} in {matrix:?} gives {yuv:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn greys_have_no_colour() {
+        for matrix in [Matrix::Bt601, Matrix::Bt709] {
+            assert_eq!(rgb_to_yuv(&Pixel::rgb(0, 0, 0), matrix), [16, 128, 128]);
+            assert_eq!(
+                rgb_to_yuv(&Pixel::rgb(255, 255, 255), matrix),
+                [235, 128, 128]
+            );
+            for level in [1, 64, 128, 200, 254] {
+                let [_, u, v] = rgb_to_yuv(&Pixel::rgb(level, level, level), matrix);
+                assert_eq!((u, v), (128, 128), "grey {level} in {matrix:?}");
+            }
+        }
+        // the matrices only differ for colours
+        let red = Pixel::rgb(255, 0, 0);
+        assert_ne!(
+            rgb_to_yuv(&red, Matrix::Bt601),
+            rgb_to_yuv(&red, Matrix::Bt709)
+        );
+    }
+
+    #[test]
+    fn chroma_filter_at_edges() {
+        let [red, green, blue] = [0xff0000, 0x00ff00, 0x0000ff].map(Pixel::from_hex);
+        let [r, g, b] =
+            [red, green, blue].map(|pixel| rgb_to_yuv(&pixel, Matrix::Bt601).map(|c| c as u32));
+        let filter = |before: [u32; 3], here: [u32; 3], after: [u32; 3], idx: usize| {
+            ((before[idx] + 2 * here[idx] + after[idx] + 2) >> 2) as u8
+        };
+
+        // a lone pixel is its own neighbour on both sides
+        let mut yuyv = [0; 4];
+        encode_row(&[red], &mut yuyv, Matrix::Bt601);
+        assert_eq!(yuyv, [r[0] as u8, r[1] as u8, r[0] as u8, r[2] as u8]);
+
+        // three pixels: the first pair repeats the first pixel on the left, and the odd last pixel is paired
+        // with itself and repeats itself on the right
+        let mut yuyv = [0; 8];
+        encode_row(&[red, green, blue], &mut yuyv, Matrix::Bt601);
+        assert_eq!(
+            yuyv,
+            [
+                r[0] as u8,
+                filter(r, r, g, 1),
+                g[0] as u8,
+                filter(r, r, g, 2),
+                b[0] as u8,
+                filter(g, b, b, 1),
+                b[0] as u8,
+                filter(g, b, b, 2),
+            ]
+        );
+
+        // four pixels: the second pair's chroma reaches back into the first pair
+        let mut yuyv = [0; 8];
+        encode_row(&[red, green, blue, red], &mut yuyv, Matrix::Bt601);
+        assert_eq!(yuyv[5], filter(g, b, r, 1));
+        assert_eq!(yuyv[7], filter(g, b, r, 2));
+    }
+
+    #[test]
+    fn pixel_format_round_trip() {
+        let mut row = [0; 8];
+        for color in COLOURS {
+            let pixel = Pixel::from_hex(color);
+            for x in 0..4 {
+                Yuyv::encode(&pixel, &mut row, x);
+                let decoded = Yuyv::decode(&row, x);
+                let error = [
+                    decoded.r.abs_diff(pixel.r),
+                    decoded.g.abs_diff(pixel.g),
+                    decoded.b.abs_diff(pixel.b),
+                ];
+                assert!(
+                    error.iter().all(|&error| error <= 2),
+                    "{color:06x} came back as {decoded:?}"
+                );
+                assert_eq!(decoded.a, 255);
+            }
+        }
+        // pixels in a pair keep their own brightness
+        Yuyv::encode(&Pixel::rgb(0, 0, 0), &mut row, 0);
+        Yuyv::encode(&Pixel::rgb(255, 255, 255), &mut row, 1);
+        assert_eq!(Yuyv::decode(&row, 0), Pixel::rgb(0, 0, 0));
+        assert_eq!(Yuyv::decode(&row, 1), Pixel::rgb(255, 255, 255));
+    }
+
+    #[test]
+    fn xfb_rows() {
+        // a 64 by 32 picture doubled into the middle of a 128 by 96 XFB, with 16 rows of bars above and below
+        let mut renderer = Renderer::new();
+        renderer.resize(64, 32);
+        renderer.generate_background();
+        renderer.fill_buffer();
+        let (width, height) = (128, 96);
+        let draw = |xfb: &mut [u8], rows: Range<u32>| unsafe {
+            mn_draw_into_xfb(
+                &renderer,
+                xfb.as_mut_ptr(),
+                width,
+                height,
+                0x102030,
+                rows.start,
+                rows.end,
+            );
+        };
+
+        let mut full = vec![0; 128 * 96 * 2];
+        draw(&mut full, 0..32);
+        let mut scaled = FrameBuffer::new(128, 96);
+        let mut scaler = Scaler::new((64, 32), (128, 96), ScaleMode::Fit, Filter::Bilinear);
+        scaler.bars = Pixel::from_hex(0x102030);
+        scaler.scale(renderer.buffer(), &mut scaled);
+        assert_eq!(full, encode(&scaled, Matrix::Bt601));
+
+        // rows 8 to 11 of the picture are rows 32 to 39 of the XFB, give or take the filter reaching a row further
+        let stride = 128 * 2;
+        let mut partial = vec![0xaa; 128 * 96 * 2];
+        draw(&mut partial, 8..12);
+        for (idx_row, (partial, full)) in
+            partial.chunks(stride).zip(full.chunks(stride)).enumerate()
+        {
+            if (32..40).contains(&idx_row) {
+                assert_eq!(partial, full, "row {idx_row}");
+            } else if !(31..42).contains(&idx_row) {
+                assert!(partial.iter().all(|&byte| byte == 0xaa), "row {idx_row}");
+            }
+        }
+
+        // the first row of the picture brings the bars above it along
+        let mut partial = vec![0xaa; 128 * 96 * 2];
+        draw(&mut partial, 0..1);
+        assert_eq!(partial[..16 * stride], full[..16 * stride]);
+        assert!(partial[20 * stride..].iter().all(|&byte| byte == 0xaa));
+    }
+}